@@ -0,0 +1,199 @@
+use super::{lumps::parse_entities_str, writer::Builder, LumpType, RawMap};
+use crate::{
+    map::{wad_name, Entities},
+    miptex::MipTexture,
+    text::{cstr_bytes, detect, encode},
+    wad::{Archive, EntryType},
+};
+use std::io::{Error as IOError, ErrorKind, Result as IOResult};
+
+const MISSING_OFFSET: u32 = u32::MAX; // compilers write -1 for textures they haven't found
+const MIPTEX_NAME_SIZE: usize = 24; // name, width and height
+const MIPTEX_HEADER_SIZE: usize = 40;
+const WAD_KEY: &str = "wad";
+
+fn invalid_data(msg: &str) -> IOError {
+    IOError::new(ErrorKind::InvalidData, msg)
+}
+
+fn read_u32(data: &[u8], i: usize) -> IOResult<u32> {
+    data.get(4 * i..4 * i + 4)
+        .map(|x| u32::from_le_bytes([x[0], x[1], x[2], x[3]]))
+        .ok_or_else(|| invalid_data("Invalid textures lump"))
+}
+
+// Each texture spans until the next one as the lump has no sizes
+fn split_textures(lump: &[u8]) -> IOResult<Vec<Option<Vec<u8>>>> {
+    let offsets_num = read_u32(lump, 0)? as usize;
+    let offsets = (1..=offsets_num)
+        .map(|i| read_u32(lump, i))
+        .collect::<IOResult<Vec<_>>>()?;
+    let mut starts: Vec<usize> = offsets
+        .iter()
+        .filter(|&&x| x != MISSING_OFFSET)
+        .map(|&x| x as usize)
+        .collect();
+    starts.sort_unstable();
+
+    offsets
+        .iter()
+        .map(|&offset| {
+            if offset == MISSING_OFFSET {
+                return Ok(None);
+            }
+            let start = offset as usize;
+            let end = starts
+                .iter()
+                .copied()
+                .find(|&x| x > start)
+                .unwrap_or(lump.len());
+            lump.get(start..end)
+                .map(|x| Some(x.to_vec()))
+                .ok_or_else(|| invalid_data("Invalid texture offset"))
+        })
+        .collect()
+}
+
+fn join_textures(textures: &[Option<Vec<u8>>]) -> Vec<u8> {
+    let mut offset = 4 + 4 * textures.len();
+    let mut out = Vec::new();
+    out.extend_from_slice(&(textures.len() as u32).to_le_bytes());
+    for texture in textures {
+        match texture {
+            Some(texture) => {
+                out.extend_from_slice(&(offset as u32).to_le_bytes());
+                offset += texture.len();
+            }
+            None => out.extend_from_slice(&MISSING_OFFSET.to_le_bytes()),
+        }
+    }
+    textures
+        .iter()
+        .flatten()
+        .for_each(|x| out.extend_from_slice(x));
+    out
+}
+
+// Textures are external (header only) if compiler hasn't found them in `wadinclude` wads
+fn parse_name(texture: &[u8]) -> IOResult<(String, bool)> {
+    let miptex = MipTexture::parse(texture).map_err(|_| invalid_data("Invalid miptex"))?;
+    Ok((miptex.name().to_string(), !miptex.is_empty()))
+}
+
+fn find_miptex<'a>(archive: &'a Archive, name: &str) -> Option<&'a [u8]> {
+    archive
+        .get_by_name(name)
+        .filter(|e| e.etype() == EntryType::MipTexture)
+        .map(|e| e.data())
+}
+
+fn is_same_wad(path: &str, name: &str) -> bool {
+    wad_name(path).eq_ignore_ascii_case(wad_name(name))
+}
+
+fn offset_in(s: &str, part: &str) -> usize {
+    part.as_ptr() as usize - s.as_ptr() as usize
+}
+
+// Only the value of the key is replaced, the rest of the lump is kept byte to byte
+fn rewrite_wad_key<F: FnOnce(Vec<&str>) -> Vec<String>>(
+    lump: &[u8],
+    rewrite: F,
+) -> IOResult<Vec<u8>> {
+    let source = parse_entities_str(lump).map_err(|_| invalid_data("Invalid entities lump"))?;
+    let s = source.as_ref();
+    let entities = Entities::parse(s).map_err(|_| invalid_data("Invalid entities lump"))?;
+    let (worldspawn, classname) = entities
+        .worldspawn()
+        .and_then(|e| Some((e, e.classname()?)))
+        .ok_or_else(|| invalid_data("No worldspawn entity"))?;
+
+    let value = rewrite(worldspawn.wads().collect()).join(";");
+
+    let (start, end, replacement) = match worldspawn.get(WAD_KEY) {
+        Some(old) => {
+            let start = offset_in(s, old);
+            (start, start + old.len(), value)
+        }
+        None => {
+            let end = offset_in(s, classname) + classname.len() + 1; // closing quote
+            (end, end, format!("\n\"{}\" \"{}\"", WAD_KEY, value))
+        }
+    };
+
+    let rewritten = [&s[..start], &replacement, &s[end..]].concat();
+    let mut out = encode(&rewritten, detect(cstr_bytes(lump)));
+    out.push(0);
+    Ok(out)
+}
+
+// Copies external textures found in wads (first wad wins) into the bsp like `wadinclude` does.
+// Wads are given with the names as the worldspawn references them and are removed from it.
+pub fn embed_textures(bsp: &[u8], wads: &[(&str, &Archive)]) -> IOResult<(Vec<u8>, Vec<String>)> {
+    let map = RawMap::parse(bsp).map_err(|_| invalid_data("Invalid bsp header"))?;
+    let mut textures = split_textures(map.lump_data(LumpType::Textures))?;
+
+    let mut embedded = Vec::new();
+    for texture in textures.iter_mut().flatten() {
+        let (name, is_embedded) = parse_name(texture)?;
+        if is_embedded {
+            continue;
+        }
+        if let Some(data) = wads
+            .iter()
+            .find_map(|(_, archive)| find_miptex(archive, &name))
+        {
+            *texture = data.to_vec();
+            embedded.push(name);
+        }
+    }
+
+    let entities = rewrite_wad_key(map.lump_data(LumpType::Entities), |paths| {
+        paths
+            .into_iter()
+            .filter(|path| !wads.iter().any(|(name, _)| is_same_wad(path, name)))
+            .map(str::to_string)
+            .collect()
+    })?;
+
+    let mut builder = Builder::from_map(&map);
+    builder.set_lump(LumpType::Textures, join_textures(&textures));
+    builder.set_lump(LumpType::Entities, entities);
+    Ok((builder.to_bytes(), embedded))
+}
+
+// Embedded textures existing in the wad become external again and the wad is referenced
+pub fn strip_textures(
+    bsp: &[u8],
+    wad_name: &str,
+    archive: &Archive,
+) -> IOResult<(Vec<u8>, Vec<String>)> {
+    let map = RawMap::parse(bsp).map_err(|_| invalid_data("Invalid bsp header"))?;
+    let mut textures = split_textures(map.lump_data(LumpType::Textures))?;
+
+    let mut stripped = Vec::new();
+    for texture in textures.iter_mut().flatten() {
+        let (name, is_embedded) = parse_name(texture)?;
+        if !is_embedded {
+            continue;
+        }
+        if find_miptex(archive, &name).is_some() {
+            texture.truncate(MIPTEX_NAME_SIZE);
+            texture.resize(MIPTEX_HEADER_SIZE, 0); // zero offsets
+            stripped.push(name);
+        }
+    }
+
+    let entities = rewrite_wad_key(map.lump_data(LumpType::Entities), |paths| {
+        let mut paths: Vec<String> = paths.into_iter().map(str::to_string).collect();
+        if !stripped.is_empty() && !paths.iter().any(|path| is_same_wad(path, wad_name)) {
+            paths.push(wad_name.to_string());
+        }
+        paths
+    })?;
+
+    let mut builder = Builder::from_map(&map);
+    builder.set_lump(LumpType::Textures, join_textures(&textures));
+    builder.set_lump(LumpType::Entities, entities);
+    Ok((builder.to_bytes(), stripped))
+}
@@ -0,0 +1,52 @@
+use super::{writer::Builder, LumpType, RawMap};
+use crate::{
+    map::Entities,
+    text::{cstr_bytes, decode, detect, encode},
+};
+use std::{
+    fs::read,
+    io::{Error as IOError, ErrorKind, Result as IOResult},
+    path::Path,
+};
+
+const BSP_EXTENSION: &str = "bsp";
+
+fn invalid_data<E: ToString>(err: E) -> IOError {
+    IOError::new(ErrorKind::InvalidData, err.to_string())
+}
+
+// Entities lump as it's stored, without the null terminator, like ripent exports it
+pub fn export_entities(bsp: &[u8]) -> IOResult<Vec<u8>> {
+    let map = RawMap::parse(bsp).map_err(|_| invalid_data("Invalid bsp header"))?;
+    Ok(cstr_bytes(map.lump_data(LumpType::Entities)).to_vec())
+}
+
+// Edited entities are validated and written in canonical form, the rest of bsp is kept
+pub fn import_entities(bsp: &[u8], ent: &[u8]) -> IOResult<Vec<u8>> {
+    let map = RawMap::parse(bsp).map_err(|_| invalid_data("Invalid bsp header"))?;
+    let ent = cstr_bytes(ent);
+    let source = decode(ent);
+    let entities = Entities::parse(&source).map_err(invalid_data)?;
+
+    let mut lump = encode(&entities.to_string(), detect(ent));
+    lump.push(0);
+    let mut builder = Builder::from_map(&map);
+    builder.set_lump(LumpType::Entities, lump);
+    Ok(builder.to_bytes())
+}
+
+// Entities text of bsp or .ent file, by the extension
+pub fn read_entities<P: AsRef<Path>>(path: P) -> IOResult<String> {
+    let path = path.as_ref();
+    let file = read(path)?;
+    let is_bsp = path
+        .extension()
+        .and_then(|x| x.to_str())
+        .is_some_and(|x| x.eq_ignore_ascii_case(BSP_EXTENSION));
+    let ent = if is_bsp {
+        export_entities(&file)?
+    } else {
+        file
+    };
+    Ok(decode(cstr_bytes(&ent)).into_owned())
+}
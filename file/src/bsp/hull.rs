@@ -0,0 +1,390 @@
+use super::lumps::{Clipnode, Plane, Vec3};
+
+pub const CONTENTS_EMPTY: i32 = -1;
+pub const CONTENTS_SOLID: i32 = -2;
+// Boxes the compilers expand brushes by for each hull, hull 0 is for points
+pub const HULL_SIZES: [(Vec3, Vec3); 4] = [
+    ((0.0, 0.0, 0.0), (0.0, 0.0, 0.0)),
+    ((-16.0, -16.0, -36.0), (16.0, 16.0, 36.0)), // standing player
+    ((-32.0, -32.0, -32.0), (32.0, 32.0, 32.0)), // large monsters
+    ((-16.0, -16.0, -18.0), (16.0, 16.0, 18.0)), // ducking player
+];
+
+const ON_EPSILON: f64 = 0.01;
+const WINDING_SIZE: f64 = 65536.0;
+const BOUNDS_MARGIN: f64 = 64.0;
+const VISIBILITY_OFFSET: f64 = 0.5; // how far from a face its neighbour leaf is probed
+const DIST_EPSILON: f64 = 0.03125; // traces stop this far before planes, like the game does
+const BACKOFF_STEP: f64 = 0.1;
+
+type Point = [f64; 3];
+
+fn dot(a: Point, b: Point) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: Point, b: Point) -> Point {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn add(a: Point, b: Point, scale: f64) -> Point {
+    [
+        a[0] + b[0] * scale,
+        a[1] + b[1] * scale,
+        a[2] + b[2] * scale,
+    ]
+}
+
+fn point(v: Vec3) -> Point {
+    [f64::from(v.0), f64::from(v.1), f64::from(v.2)]
+}
+
+fn vec3(p: Point) -> Vec3 {
+    (p[0] as f32, p[1] as f32, p[2] as f32)
+}
+
+// Half-space of a leaf, the leaf is behind the plane
+#[derive(Clone, Copy, PartialEq)]
+struct Side {
+    normal: Point,
+    dist: f64,
+}
+
+impl Side {
+    fn distance(&self, p: Point) -> f64 {
+        dot(self.normal, p) - self.dist
+    }
+
+    fn flipped(self) -> Self {
+        Self {
+            normal: [-self.normal[0], -self.normal[1], -self.normal[2]],
+            dist: -self.dist,
+        }
+    }
+
+    // Huge quad on the plane, clipped down to the face afterwards
+    fn base_winding(&self) -> Vec<Point> {
+        let n = self.normal;
+        let up = if n[2].abs() > n[0].abs() && n[2].abs() > n[1].abs() {
+            [1.0, 0.0, 0.0]
+        } else {
+            [0.0, 0.0, 1.0]
+        };
+        let up = add(up, n, -dot(up, n));
+        let length = dot(up, up).sqrt();
+        let up = [
+            up[0] / length * WINDING_SIZE,
+            up[1] / length * WINDING_SIZE,
+            up[2] / length * WINDING_SIZE,
+        ];
+        let right = cross(up, n);
+        let origin = [n[0] * self.dist, n[1] * self.dist, n[2] * self.dist];
+        vec![
+            add(add(origin, right, -1.0), up, 1.0),
+            add(add(origin, right, 1.0), up, 1.0),
+            add(add(origin, right, 1.0), up, -1.0),
+            add(add(origin, right, -1.0), up, -1.0),
+        ]
+    }
+
+    // Keeps the part of the winding behind the plane
+    fn clip(&self, winding: &[Point]) -> Vec<Point> {
+        let mut clipped = Vec::with_capacity(winding.len() + 1);
+        for (i, &p1) in winding.iter().enumerate() {
+            let p2 = winding[(i + 1) % winding.len()];
+            let (d1, d2) = (self.distance(p1), self.distance(p2));
+            if d1 <= ON_EPSILON {
+                clipped.push(p1);
+            }
+            if (d1 > ON_EPSILON && d2 < -ON_EPSILON) || (d1 < -ON_EPSILON && d2 > ON_EPSILON) {
+                let t = d1 / (d1 - d2);
+                clipped.push([
+                    p1[0] + (p2[0] - p1[0]) * t,
+                    p1[1] + (p2[1] - p1[1]) * t,
+                    p1[2] + (p2[2] - p1[2]) * t,
+                ]);
+            }
+        }
+        clipped
+    }
+}
+
+impl From<&Plane> for Side {
+    fn from(plane: &Plane) -> Self {
+        Self {
+            normal: point(plane.normal),
+            dist: f64::from(plane.dist),
+        }
+    }
+}
+
+// Result of moving a point through a hull, the same as `trace_t` of the game
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Trace {
+    pub fraction: f32, // part of the way done before hitting, 1 if nothing is hit
+    pub end: Vec3,
+    pub normal: Vec3, // of the plane hit
+    pub start_solid: bool,
+    pub all_solid: bool,
+}
+
+impl Trace {
+    // Nothing was hit on the way to the end
+    pub const fn clear(end: Vec3) -> Self {
+        Self {
+            fraction: 1.0,
+            end,
+            normal: (0.0, 0.0, 0.0),
+            start_solid: false,
+            all_solid: false,
+        }
+    }
+}
+
+// Clipnodes of a model for one of the hulls 1-3, what the player collides with
+pub struct Hull<'a> {
+    planes: &'a [Plane],
+    clipnodes: &'a [Clipnode],
+    headnode: i32,
+}
+
+impl<'a> Hull<'a> {
+    pub const fn new(planes: &'a [Plane], clipnodes: &'a [Clipnode], headnode: i32) -> Self {
+        Self {
+            planes,
+            clipnodes,
+            headnode,
+        }
+    }
+
+    // Broken nodes are treated as solid, so nothing leaks through them
+    fn node(&self, node: i32) -> Option<(&Clipnode, Side)> {
+        let clipnode = self.clipnodes.get(node as usize)?;
+        let plane = self.planes.get(clipnode.plane_id)?;
+        Some((clipnode, Side::from(plane)))
+    }
+
+    pub fn point_contents(&self, p: Vec3) -> i32 {
+        self.contents(point(p))
+    }
+
+    fn contents(&self, p: Point) -> i32 {
+        self.contents_from(self.headnode, p)
+    }
+
+    fn contents_from(&self, node: i32, p: Point) -> i32 {
+        let mut node = node;
+        for _ in 0..=self.clipnodes.len() {
+            if node < 0 {
+                return node;
+            }
+            node = match self.node(node) {
+                Some((clipnode, side)) => clipnode.children[usize::from(side.distance(p) < 0.0)],
+                None => return CONTENTS_SOLID,
+            };
+        }
+        CONTENTS_SOLID
+    }
+
+    // Moves the point until it hits solid space, the way `SV_RecursiveHullCheck` does
+    pub fn trace(&self, start: Vec3, end: Vec3) -> Trace {
+        let (start, end) = (point(start), point(end));
+        let mut trace = Trace {
+            all_solid: true,
+            ..Trace::clear(vec3(end))
+        };
+        self.check(self.headnode, (0.0, 1.0), (start, end), 0, &mut trace);
+        if trace.all_solid {
+            trace.start_solid = true;
+            trace.fraction = 0.0;
+            trace.end = vec3(start);
+        }
+        trace
+    }
+
+    // Returns false when the trace has hit something and the rest of the way doesn't matter
+    fn check(
+        &self,
+        node: i32,
+        (f1, f2): (f64, f64),
+        (p1, p2): (Point, Point),
+        depth: usize,
+        trace: &mut Trace,
+    ) -> bool {
+        if node < 0 {
+            if node == CONTENTS_SOLID {
+                trace.start_solid = true;
+            } else {
+                trace.all_solid = false;
+            }
+            return true;
+        }
+        let (clipnode, side) = match self.node(node).filter(|_| depth <= self.clipnodes.len()) {
+            Some(node) => node,
+            None => {
+                trace.start_solid = true;
+                return true;
+            }
+        };
+
+        let (t1, t2) = (side.distance(p1), side.distance(p2));
+        if t1 >= 0.0 && t2 >= 0.0 {
+            return self.check(clipnode.children[0], (f1, f2), (p1, p2), depth + 1, trace);
+        }
+        if t1 < 0.0 && t2 < 0.0 {
+            return self.check(clipnode.children[1], (f1, f2), (p1, p2), depth + 1, trace);
+        }
+
+        // Splitting is put a bit off the plane on the near side
+        let mut frac = if t1 < 0.0 {
+            (t1 + DIST_EPSILON) / (t1 - t2)
+        } else {
+            (t1 - DIST_EPSILON) / (t1 - t2)
+        }
+        .clamp(0.0, 1.0);
+        let split = |frac: f64| {
+            let mid = [
+                p1[0] + (p2[0] - p1[0]) * frac,
+                p1[1] + (p2[1] - p1[1]) * frac,
+                p1[2] + (p2[2] - p1[2]) * frac,
+            ];
+            (f1 + (f2 - f1) * frac, mid)
+        };
+        let (mut midf, mut mid) = split(frac);
+        let near = usize::from(t1 < 0.0);
+
+        if !self.check(
+            clipnode.children[near],
+            (f1, midf),
+            (p1, mid),
+            depth + 1,
+            trace,
+        ) {
+            return false;
+        }
+        if self.contents_from(clipnode.children[near ^ 1], mid) != CONTENTS_SOLID {
+            return self.check(
+                clipnode.children[near ^ 1],
+                (midf, f2),
+                (mid, p2),
+                depth + 1,
+                trace,
+            );
+        }
+        if trace.all_solid {
+            return false; // never got out of the solid area
+        }
+
+        // The other side is solid, so this is the impact point
+        let plane = if near == 0 { side } else { side.flipped() };
+        trace.normal = vec3(plane.normal);
+        while self.contents(mid) == CONTENTS_SOLID {
+            // Shouldn't happen, but does with float errors near corners
+            frac -= BACKOFF_STEP;
+            if frac < 0.0 {
+                break;
+            }
+            let (f, p) = split(frac);
+            midf = f;
+            mid = p;
+        }
+        trace.fraction = midf as f32;
+        trace.end = vec3(mid);
+        false
+    }
+
+    // Faces of solid leaves facing non-solid space. Solid space outside of the map is closed by
+    // the bounds (with some margin), but faces on the bounds themselves aren't returned.
+    pub fn polygons(&self, mins: Vec3, maxs: Vec3) -> Vec<Vec<Vec3>> {
+        let (mins, maxs) = (point(mins), point(maxs));
+        let bounds: Vec<Side> = (0..3)
+            .flat_map(|axis| {
+                let mut normal = [0.0; 3];
+                normal[axis] = 1.0;
+                let side = Side {
+                    normal,
+                    dist: maxs[axis] + BOUNDS_MARGIN,
+                };
+                let opposite = Side {
+                    dist: BOUNDS_MARGIN - mins[axis],
+                    ..side.flipped()
+                };
+                [side, opposite]
+            })
+            .collect();
+
+        let mut polygons = Vec::new();
+        self.collect_polygons(self.headnode, &mut Vec::new(), &bounds, &mut polygons);
+        polygons
+            .into_iter()
+            .map(|polygon| polygon.into_iter().map(vec3).collect())
+            .collect()
+    }
+
+    fn collect_polygons(
+        &self,
+        node: i32,
+        sides: &mut Vec<Side>,
+        bounds: &[Side],
+        polygons: &mut Vec<Vec<Point>>,
+    ) {
+        if node < 0 {
+            if node == CONTENTS_SOLID {
+                self.leaf_polygons(sides, bounds, polygons);
+            }
+            return;
+        }
+        // Trees deeper than the number of nodes have cycles
+        if sides.len() > self.clipnodes.len() {
+            return;
+        }
+        if let Some((clipnode, side)) = self.node(node) {
+            sides.push(side.flipped());
+            self.collect_polygons(clipnode.children[0], sides, bounds, polygons);
+            sides.pop();
+            sides.push(side);
+            self.collect_polygons(clipnode.children[1], sides, bounds, polygons);
+            sides.pop();
+        }
+    }
+
+    fn leaf_polygons(&self, sides: &[Side], bounds: &[Side], polygons: &mut Vec<Vec<Point>>) {
+        for (i, side) in sides.iter().enumerate() {
+            if sides[..i].contains(side) {
+                continue;
+            }
+            let mut winding = side.base_winding();
+            for other in sides.iter().chain(bounds).filter(|&x| x != side) {
+                winding = other.clip(&winding);
+                if winding.len() < 3 {
+                    break;
+                }
+            }
+            if winding.len() >= 3 && self.is_visible(side, &winding) {
+                polygons.push(winding);
+            }
+        }
+    }
+
+    // Faces between solid leaves the tree has split aren't seen, so the other side is probed
+    // at the center and near the corners
+    fn is_visible(&self, side: &Side, winding: &[Point]) -> bool {
+        let scale = 1.0 / winding.len() as f64;
+        let center = winding
+            .iter()
+            .fold([0.0; 3], |center, &p| add(center, p, scale));
+        std::iter::once(center)
+            .chain(winding.iter().map(|&p| {
+                [
+                    (p[0] * 3.0 + center[0]) / 4.0,
+                    (p[1] * 3.0 + center[1]) / 4.0,
+                    (p[2] * 3.0 + center[2]) / 4.0,
+                ]
+            }))
+            .any(|p| self.contents(add(p, side.normal, VISIBILITY_OFFSET)) != CONTENTS_SOLID)
+    }
+}
@@ -1,13 +1,14 @@
 // TODO : Reformat to human-read structures
 
-use crate::miptex::MipTexture;
+use crate::{miptex::MipTexture, text::decode};
 use nom::{
     bytes::complete::{take, take_until},
-    combinator::{map, map_res},
+    combinator::map,
     multi::{count, many0},
-    number::complete::{le_f32, le_i32, le_u16, le_u32, le_u8},
+    number::complete::{le_f32, le_i16, le_i32, le_u16, le_u32, le_u8},
     sequence::tuple,
 };
+use std::borrow::Cow;
 
 pub type Vec3 = (f32, f32, f32);
 type Input<'a> = &'a [u8];
@@ -32,51 +33,96 @@ pub struct Face {
     pub lightmap: usize,
 }
 
+pub struct Plane {
+    pub normal: Vec3,
+    pub dist: f32,
+}
+
+// Node of a clip hull tree, negative children are contents of leaves
+pub struct Clipnode {
+    pub plane_id: usize,
+    pub children: [i32; 2], // in front of the plane and behind it
+}
+
 pub struct Model {
+    pub mins: Vec3,
+    pub maxs: Vec3,
     pub origin: Vec3,
+    pub headnodes: [i32; 4], // bsp node of hull 0, clipnodes of hulls 1-3
     pub face_id: usize,
     pub face_num: usize,
 }
 
-pub fn parse_entities_str(i: &[u8]) -> OnlyResult<&str> {
-    let (_, s) = map_res(take_until("\0"), std::str::from_utf8)(i)?;
+// Text up to the null terminator, decoded as UTF-8 or Windows-1252 (e.g. `message` of old maps)
+pub fn parse_entities_str(i: &[u8]) -> OnlyResult<'_, Cow<'_, str>> {
+    let (_, s) = map(take_until("\0"), decode)(i)?;
     Ok(s)
 }
 
-fn parse_vec3(i: &[u8]) -> ParseResult<Vec3> {
+fn parse_vec3(i: &[u8]) -> ParseResult<'_, Vec3> {
     tuple((le_f32, le_f32, le_f32))(i)
 }
 
-pub fn parse_vertices(i: &[u8]) -> OnlyResult<Vec<Vec3>> {
+pub fn parse_vertices(i: &[u8]) -> OnlyResult<'_, Vec<Vec3>> {
     let (_, vertices) = many0(parse_vec3)(i)?;
     Ok(vertices)
 }
 
-fn parse_edge(i: &[u8]) -> ParseResult<(u16, u16)> {
+fn parse_edge(i: &[u8]) -> ParseResult<'_, (u16, u16)> {
     tuple((le_u16, le_u16))(i)
 }
 
-pub fn parse_edges(i: &[u8]) -> OnlyResult<Vec<(u16, u16)>> {
+pub fn parse_edges(i: &[u8]) -> OnlyResult<'_, Vec<(u16, u16)>> {
     let (_, edges) = many0(parse_edge)(i)?;
     Ok(edges)
 }
 
-pub fn parse_surfedges(i: &[u8]) -> OnlyResult<Vec<i32>> {
+pub fn parse_surfedges(i: &[u8]) -> OnlyResult<'_, Vec<i32>> {
     let (_, surfedges) = many0(le_i32)(i)?;
     Ok(surfedges)
 }
 
-fn parse_normal_from_plane(i: &[u8]) -> ParseResult<Vec3> {
+fn parse_normal_from_plane(i: &[u8]) -> ParseResult<'_, Vec3> {
     let (i, (normal, _, _)) = tuple((parse_vec3, le_f32, le_u32))(i)?;
     Ok((i, normal))
 }
 
-pub fn parse_normals_from_planes(i: &[u8]) -> OnlyResult<Vec<Vec3>> {
+pub fn parse_normals_from_planes(i: &[u8]) -> OnlyResult<'_, Vec<Vec3>> {
     let (_, normals) = many0(parse_normal_from_plane)(i)?;
     Ok(normals)
 }
 
-fn parse_texinfo(i: &[u8]) -> ParseResult<TexInfo> {
+fn parse_plane(i: &[u8]) -> ParseResult<'_, Plane> {
+    let (i, (normal, dist, _)) = tuple((parse_vec3, le_f32, le_u32))(i)?;
+    Ok((i, Plane { normal, dist }))
+}
+
+pub fn parse_planes(i: &[u8]) -> OnlyResult<'_, Vec<Plane>> {
+    let (_, planes) = many0(parse_plane)(i)?;
+    Ok(planes)
+}
+
+fn parse_clipnode(i: &[u8]) -> ParseResult<'_, Clipnode> {
+    let (i, (plane_id, front, back)) = tuple((
+        map(le_u32, |x| x as usize),
+        map(le_i16, i32::from),
+        map(le_i16, i32::from),
+    ))(i)?;
+    Ok((
+        i,
+        Clipnode {
+            plane_id,
+            children: [front, back],
+        },
+    ))
+}
+
+pub fn parse_clipnodes(i: &[u8]) -> OnlyResult<'_, Vec<Clipnode>> {
+    let (_, clipnodes) = many0(parse_clipnode)(i)?;
+    Ok(clipnodes)
+}
+
+fn parse_texinfo(i: &[u8]) -> ParseResult<'_, TexInfo> {
     let (i, (vs, ss, vt, st, texture_id, _)) = tuple((
         parse_vec3,
         le_f32,
@@ -97,12 +143,12 @@ fn parse_texinfo(i: &[u8]) -> ParseResult<TexInfo> {
     ))
 }
 
-pub fn parse_texinfos(i: &[u8]) -> OnlyResult<Vec<TexInfo>> {
+pub fn parse_texinfos(i: &[u8]) -> OnlyResult<'_, Vec<TexInfo>> {
     let (_, texinfos) = many0(parse_texinfo)(i)?;
     Ok(texinfos)
 }
 
-fn parse_face(i: &[u8]) -> ParseResult<Face> {
+fn parse_face(i: &[u8]) -> ParseResult<'_, Face> {
     let (i, (plane_id, side, surfedge_id, surfedge_num, texinfo_id, _, _, _, _, lightmap)) =
         tuple((
             map(le_u16, |x| x as usize),
@@ -129,19 +175,18 @@ fn parse_face(i: &[u8]) -> ParseResult<Face> {
     ))
 }
 
-pub fn parse_faces(i: &[u8]) -> OnlyResult<Vec<Face>> {
+pub fn parse_faces(i: &[u8]) -> OnlyResult<'_, Vec<Face>> {
     let (_, faces) = many0(parse_face)(i)?;
     Ok(faces)
 }
 
-fn parse_model(i: &[u8]) -> ParseResult<Model> {
-    let (i, (_, origin, _, _, _, _, _, face_id, face_num)) = tuple((
+fn parse_model(i: &[u8]) -> ParseResult<'_, Model> {
+    let (i, ((mins, maxs), origin, headnodes, _, face_id, face_num)) = tuple((
         tuple((parse_vec3, parse_vec3)),
         parse_vec3,
-        le_u32,
-        le_u32,
-        le_u32,
-        le_u32,
+        map(tuple((le_i32, le_i32, le_i32, le_i32)), |(a, b, c, d)| {
+            [a, b, c, d]
+        }),
         le_u32,
         map(le_u32, |x| x as usize),
         map(le_u32, |x| x as usize),
@@ -149,19 +194,22 @@ fn parse_model(i: &[u8]) -> ParseResult<Model> {
     Ok((
         i,
         Model {
+            mins,
+            maxs,
             origin,
+            headnodes,
             face_id,
             face_num,
         },
     ))
 }
 
-pub fn parse_models(i: &[u8]) -> OnlyResult<Vec<Model>> {
+pub fn parse_models(i: &[u8]) -> OnlyResult<'_, Vec<Model>> {
     let (_, models) = many0(parse_model)(i)?;
     Ok(models)
 }
 
-pub fn parse_textures(lump: &[u8]) -> OnlyResult<Vec<MipTexture>> {
+pub fn parse_textures(lump: &[u8]) -> OnlyResult<'_, Vec<MipTexture<'_>>> {
     let (i, offsets_num) = map(le_u32, |x| x as usize)(lump)?;
     let (_, offsets) = count(le_u32, offsets_num)(i)?;
     offsets
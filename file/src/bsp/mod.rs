@@ -1,4 +1,8 @@
+pub mod embed;
+pub mod ent;
+pub mod hull;
 pub mod lumps; // TODO
+pub mod writer;
 
 use nom::{
     bytes::complete::take,
@@ -11,6 +15,7 @@ use nom::{
 const LUMPS_NUM: usize = 15;
 const HLBSP_VERSION: u32 = 30;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LumpType {
     Entities,
     Planes,
@@ -54,7 +59,7 @@ pub struct RawMap<'a> {
 }
 
 impl<'a> RawMap<'a> {
-    pub fn parse(file: &'a [u8]) -> OnlyResult<Self> {
+    pub fn parse(file: &'a [u8]) -> OnlyResult<'a, Self> {
         let (_, (_, lumps)) = tuple((
             verify(le_u32, |&x| x == HLBSP_VERSION),
             count(|i| Lump::parse(i, file), LUMPS_NUM),
@@ -0,0 +1,72 @@
+use super::{LumpType, RawMap, HLBSP_VERSION, LUMPS_NUM};
+use std::{
+    fs::write,
+    io::{Result as IOResult, Write},
+    path::Path,
+};
+
+const HEADER_SIZE: usize = 4 + LUMPS_NUM * 8;
+const ALIGNMENT: usize = 4;
+
+pub struct Builder {
+    lumps: Vec<Vec<u8>>,
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self {
+            lumps: vec![Vec::new(); LUMPS_NUM],
+        }
+    }
+}
+
+impl Builder {
+    // All lumps are empty
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_map(map: &RawMap) -> Self {
+        Self {
+            lumps: map.lumps.iter().map(|lump| lump.data.to_vec()).collect(),
+        }
+    }
+
+    pub fn lump(&self, lump_type: LumpType) -> &[u8] {
+        &self.lumps[lump_type as usize]
+    }
+
+    pub fn set_lump(&mut self, lump_type: LumpType, data: Vec<u8>) {
+        self.lumps[lump_type as usize] = data;
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let data_size: usize = self.lumps.iter().map(|lump| align(lump.len())).sum();
+        let mut out = Vec::with_capacity(HEADER_SIZE + data_size);
+
+        out.extend_from_slice(&HLBSP_VERSION.to_le_bytes());
+        let mut offset = HEADER_SIZE;
+        for lump in &self.lumps {
+            out.extend_from_slice(&(offset as u32).to_le_bytes());
+            out.extend_from_slice(&(lump.len() as u32).to_le_bytes());
+            offset += align(lump.len());
+        }
+        for lump in &self.lumps {
+            out.extend_from_slice(lump);
+            out.resize(align(out.len()), 0);
+        }
+        out
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> IOResult<()> {
+        writer.write_all(&self.to_bytes())
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> IOResult<()> {
+        write(path, self.to_bytes())
+    }
+}
+
+const fn align(size: usize) -> usize {
+    size.div_ceil(ALIGNMENT) * ALIGNMENT
+}
@@ -1,8 +1,9 @@
+use crate::vfs::FileSystem;
 use arraylib::Array;
 use image::{ImageError, ImageResult};
 use std::{
     io::{Error as IOError, ErrorKind},
-    path::Path,
+    path::{Path, PathBuf},
 };
 
 const EXTENSION: &str = "tga";
@@ -15,12 +16,29 @@ pub struct Cubemap {
 
 impl Cubemap {
     pub fn read<S: AsRef<str>, P: AsRef<Path>>(name: S, path: P) -> ImageResult<Self> {
+        Self::read_with(name, |file_name| Some(path.as_ref().join(file_name)))
+    }
+
+    // Every side is resolved on its own, so they may come from different game directories
+    pub fn read_fs<S: AsRef<str>>(name: S, fs: &FileSystem) -> ImageResult<Self> {
+        Self::read_with(name, |file_name| fs.find_sky(file_name))
+    }
+
+    fn read_with<S: AsRef<str>, F: Fn(&str) -> Option<PathBuf>>(
+        name: S,
+        resolve: F,
+    ) -> ImageResult<Self> {
         let mut dimension = 0;
         let sides: Vec<_> = SIDES
             .iter()
             .map(|postfix| {
                 let file_name = format!("{}{}.{}", name.as_ref(), postfix, EXTENSION);
-                let file_path = path.as_ref().join(file_name);
+                let file_path = resolve(&file_name).ok_or_else(|| {
+                    ImageError::IoError(IOError::new(
+                        ErrorKind::NotFound,
+                        format!("Skybox side {} not found.", file_name),
+                    ))
+                })?;
                 let image = image::open(file_path)?.to_rgba8();
                 if dimension == 0 {
                     dimension = image.width();
@@ -46,7 +64,7 @@ impl Cubemap {
                 Ok(image.into_raw())
             })
             .collect::<ImageResult<_>>()?;
-        let sides = <[Vec<u8>; 6]>::from_iter(sides.into_iter());
+        let sides = <[Vec<u8>; 6]>::from_iter(sides);
 
         Ok(Self { dimension, sides })
     }
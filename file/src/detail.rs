@@ -0,0 +1,51 @@
+use nom::{
+    bytes::complete::take_till1,
+    character::complete::{multispace0, space1},
+    combinator::{map, opt},
+    number::complete::float,
+    sequence::{preceded, tuple},
+};
+
+type Input<'a> = &'a str;
+type ParseError<'a> = nom::error::VerboseError<Input<'a>>;
+type ParseResult<'a, O> = nom::IResult<Input<'a>, O, ParseError<'a>>;
+
+// Line of `maps/<map>_detail.txt`: `<texture> <detail texture> <x scale> <y scale>`
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetailTexture<'a> {
+    pub texture: &'a str,
+    pub detail: &'a str, // relative to `gfx`, without extension
+    pub scale: (f32, f32),
+}
+
+impl<'a> DetailTexture<'a> {
+    fn parse(i: &'a str) -> ParseResult<'a, Self> {
+        let token = |i| take_till1(|c: char| c.is_whitespace())(i);
+        map(
+            tuple((
+                token,
+                preceded(space1, token),
+                preceded(space1, float),
+                opt(preceded(space1, float)),
+            )),
+            |(texture, detail, x_scale, y_scale)| Self {
+                texture,
+                detail,
+                scale: (x_scale, y_scale.unwrap_or(x_scale)),
+            },
+        )(i)
+    }
+
+    // Comments and malformed lines are skipped like the engine does
+    pub fn parse_file(file: &'a str) -> Vec<Self> {
+        file.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with("//"))
+            .filter_map(|line| {
+                let (rest, detail) = Self::parse(line).ok()?;
+                let (rest, _) = multispace0::<_, ParseError>(rest).ok()?;
+                (rest.is_empty() || rest.starts_with("//")).then_some(detail)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,538 @@
+use crate::{
+    bsp::lumps::Vec3,
+    map::{parse_vec3, Entities, SyntaxError},
+};
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, tag_no_case, take_till, take_while1},
+    character::complete::{char as character, multispace1, not_line_ending},
+    combinator::{cut, eof, map, opt, value},
+    error::{context, VerboseErrorKind},
+    multi::{many0, separated_list1},
+    number::complete::recognize_float,
+    sequence::{delimited, pair, preceded, terminated, tuple},
+};
+use std::fmt;
+
+type Input<'a> = &'a str;
+type ParseError<'a> = nom::error::VerboseError<Input<'a>>;
+type ParseResult<'a, O> = nom::IResult<Input<'a>, O, ParseError<'a>>;
+
+// Keys written by Hammer and compilers, they're not listed in game definitions
+const BUILTIN_KEYS: [&str; 5] = ["classname", "origin", "model", "spawnflags", "wad"];
+const COMPILER_KEY_PREFIXES: [&str; 2] = ["_", "zhlt_"];
+const MAX_BASE_DEPTH: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClassType {
+    Base,
+    Point,
+    Solid,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyType {
+    String,
+    Integer,
+    Float,
+    Choices,
+    Flags,
+    Color255,
+    Color1,
+    TargetSource,
+    TargetDestination,
+    Studio,
+    Sprite,
+    Sound,
+    Decal,
+    Other(String),
+}
+
+impl From<&str> for KeyType {
+    fn from(name: &str) -> Self {
+        match name.to_ascii_lowercase().as_str() {
+            "string" => Self::String,
+            "integer" => Self::Integer,
+            "float" => Self::Float,
+            "choices" => Self::Choices,
+            "flags" => Self::Flags,
+            "color255" => Self::Color255,
+            "color1" => Self::Color1,
+            "target_source" => Self::TargetSource,
+            "target_destination" => Self::TargetDestination,
+            "studio" => Self::Studio,
+            "sprite" => Self::Sprite,
+            "sound" => Self::Sound,
+            "decal" => Self::Decal,
+            _ => Self::Other(name.to_string()),
+        }
+    }
+}
+
+impl KeyType {
+    // Types without a format (strings, file names) accept anything
+    fn accepts(&self, value: &str) -> bool {
+        let numbers = |s: &str| -> Option<Vec<f32>> {
+            s.split_whitespace().map(|x| x.parse().ok()).collect()
+        };
+        match self {
+            Self::Integer => value.trim().parse::<i64>().is_ok(),
+            Self::Flags => value.trim().parse::<u32>().is_ok(),
+            Self::Float => value.trim().parse::<f32>().is_ok(),
+            // The 4th number is brightness, which isn't limited
+            Self::Color255 => numbers(value).is_some_and(|x| {
+                (3..=4).contains(&x.len()) && x[..3].iter().all(|c| (0.0..=255.0).contains(c))
+            }),
+            Self::Color1 => numbers(value).is_some_and(|x| (3..=4).contains(&x.len())),
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for KeyType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Other(name) => write!(f, "{}", name),
+            ktype => write!(f, "{}", format!("{:?}", ktype).to_ascii_lowercase()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Choice {
+    pub value: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Flag {
+    pub bit: u32,
+    pub name: String,
+    pub default: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Key {
+    pub name: String,
+    pub ktype: KeyType,
+    pub display_name: Option<String>,
+    pub default: Option<String>,
+    pub description: Option<String>,
+    pub choices: Vec<Choice>,
+    pub flags: Vec<Flag>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Class {
+    pub ctype: ClassType,
+    pub name: String,
+    pub description: Option<String>,
+    pub bases: Vec<String>,
+    pub size: Option<(Vec3, Vec3)>,
+    pub color: Option<(u8, u8, u8)>,
+    pub keys: Vec<Key>,
+}
+
+// Whitespaces and `//` comments
+fn space(i: &str) -> ParseResult<'_, ()> {
+    map(
+        many0(alt((multispace1, preceded(tag("//"), not_line_ending)))),
+        |_| (),
+    )(i)
+}
+
+fn ws<'a, O, F: FnMut(&'a str) -> ParseResult<'a, O>>(
+    f: F,
+) -> impl FnMut(&'a str) -> ParseResult<'a, O> {
+    preceded(space, f)
+}
+
+// Fields of key separated by `:`, any of them can be empty
+fn field<'a, O, F: FnMut(&'a str) -> ParseResult<'a, O>>(
+    f: F,
+) -> impl FnMut(&'a str) -> ParseResult<'a, Option<Option<O>>> {
+    opt(preceded(ws(character(':')), opt(ws(f))))
+}
+
+fn quoted(i: &str) -> ParseResult<'_, &str> {
+    let (string_i, _) = character('"')(i)?;
+    terminated(take_till(|c| c == '"' || c == '\n'), character('"'))(string_i).map_err(
+        |_: nom::Err<ParseError>| {
+            nom::Err::Failure(ParseError {
+                errors: vec![(i, VerboseErrorKind::Context("unterminated string"))],
+            })
+        },
+    )
+}
+
+// Long descriptions are split like `"first line" + "second line"`
+fn description(i: &str) -> ParseResult<'_, String> {
+    map(separated_list1(ws(character('+')), ws(quoted)), |parts| {
+        parts.concat()
+    })(i)
+}
+
+fn ident(i: &str) -> ParseResult<'_, &str> {
+    take_while1(|c: char| c.is_ascii_alphanumeric() || c == '_')(i)
+}
+
+fn literal(i: &str) -> ParseResult<'_, String> {
+    alt((description, map(recognize_float, str::to_string)))(i)
+}
+
+// `size(-16 -16 0, 16 16 72)`, or `size(16 16 16)` for a box centered at the origin
+fn parse_size(args: &str) -> Option<(Vec3, Vec3)> {
+    match args.split(',').collect::<Vec<_>>()[..] {
+        [mins, maxs] => Some((parse_vec3(mins)?, parse_vec3(maxs)?)),
+        [size] => {
+            let (x, y, z) = parse_vec3(size)?;
+            Some(((-x / 2.0, -y / 2.0, -z / 2.0), (x / 2.0, y / 2.0, z / 2.0)))
+        }
+        _ => None,
+    }
+}
+
+fn parse_color(args: &str) -> Option<(u8, u8, u8)> {
+    let numbers: Vec<u8> = args
+        .split_whitespace()
+        .map(|x| x.parse().ok())
+        .collect::<Option<_>>()?;
+    match numbers[..] {
+        [r, g, b] => Some((r, g, b)),
+        _ => None,
+    }
+}
+
+// Class properties like `base(Targetname, Angles)` or `studio("models/scientist.mdl")`,
+// some have no arguments at all like `halfgridsnap`
+fn helper(i: &str) -> ParseResult<'_, (&str, &str)> {
+    pair(
+        ident,
+        map(
+            opt(ws(delimited(
+                character('('),
+                take_till(|c| c == ')'),
+                cut(character(')')),
+            ))),
+            Option::unwrap_or_default,
+        ),
+    )(i)
+}
+
+// `value : "name"` of choices or `bit : "name" : default` of flags
+fn item(i: &str) -> ParseResult<'_, (String, String, Option<&str>)> {
+    tuple((
+        literal,
+        cut(preceded(ws(character(':')), description)),
+        opt(preceded(ws(character(':')), ws(recognize_float))),
+    ))(i)
+}
+
+impl Key {
+    // name(type) : "Display name" : default : "Description" = [ items ]
+    fn parse(i: &str) -> ParseResult<'_, Self> {
+        let (i, (name, ktype)) = pair(
+            ident,
+            ws(delimited(character('('), ws(ident), ws(character(')')))),
+        )(i)?;
+        let (i, (_, display_name, default, description, items)) = context(
+            "invalid key",
+            cut(tuple((
+                opt(ws(tag_no_case("readonly"))),
+                field(description),
+                field(literal),
+                field(description),
+                opt(preceded(
+                    ws(character('=')),
+                    delimited(ws(character('[')), many0(ws(item)), ws(character(']'))),
+                )),
+            ))),
+        )(i)?;
+
+        let ktype = KeyType::from(ktype);
+        let items = items.unwrap_or_default();
+        let (choices, flags) = if ktype == KeyType::Flags {
+            let flags = items
+                .into_iter()
+                .filter_map(|(bit, name, default)| {
+                    Some(Flag {
+                        bit: bit.parse().ok()?,
+                        name,
+                        default: default.is_some_and(|x| x != "0"),
+                    })
+                })
+                .collect();
+            (Vec::new(), flags)
+        } else {
+            let choices = items
+                .into_iter()
+                .map(|(value, name, _)| Choice { value, name })
+                .collect();
+            (choices, Vec::new())
+        };
+
+        Ok((
+            i,
+            Self {
+                name: name.to_string(),
+                ktype,
+                display_name: display_name.flatten(),
+                default: default.flatten(),
+                description: description.flatten(),
+                choices,
+                flags,
+            },
+        ))
+    }
+
+    // Numeric choices match regardless of the formatting, e.g. `0` and `0.0`
+    fn has_choice(&self, value: &str) -> bool {
+        let number = |s: &str| s.trim().parse::<f64>().ok();
+        self.choices.iter().any(|choice| {
+            choice.value == value || number(&choice.value).is_some_and(|x| number(value) == Some(x))
+        })
+    }
+}
+
+impl Class {
+    // @PointClass helpers... = name : "Description" [ keys ]
+    fn parse(i: &str) -> ParseResult<'_, Self> {
+        let (i, ctype) = preceded(
+            character('@'),
+            alt((
+                value(ClassType::Base, tag_no_case("BaseClass")),
+                value(ClassType::Point, tag_no_case("PointClass")),
+                value(ClassType::Solid, tag_no_case("SolidClass")),
+            )),
+        )(i)?;
+        let (i, (helpers, name, description, keys)) = context(
+            "invalid class",
+            cut(tuple((
+                many0(ws(helper)),
+                preceded(ws(character('=')), ws(ident)),
+                opt(preceded(ws(character(':')), description)),
+                delimited(
+                    ws(character('[')),
+                    many0(ws(Key::parse)),
+                    ws(character(']')),
+                ),
+            ))),
+        )(i)?;
+
+        let mut class = Self {
+            ctype,
+            name: name.to_string(),
+            description,
+            bases: Vec::new(),
+            size: None,
+            color: None,
+            keys,
+        };
+        for (helper, args) in helpers {
+            match helper.to_ascii_lowercase().as_str() {
+                "base" => class.bases.extend(
+                    args.split(',')
+                        .map(str::trim)
+                        .filter(|x| !x.is_empty())
+                        .map(str::to_string),
+                ),
+                "size" => class.size = parse_size(args),
+                "color" => class.color = parse_color(args),
+                _ => {}
+            }
+        }
+        Ok((i, class))
+    }
+}
+
+// Directives like `@mapsize(...)` or `@MaterialExclusion [...]` are skipped up to the next one
+fn unknown_directive(i: &str) -> ParseResult<'_, ()> {
+    let (i, _) = preceded(character('@'), ident)(i)?;
+    let (mut depth, mut in_quotes) = (0usize, false);
+    let mut chars = i.char_indices().peekable();
+    while let Some((pos, c)) = chars.next() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '/' if !in_quotes && chars.peek().is_some_and(|&(_, c)| c == '/') => {
+                while chars.next_if(|&(_, c)| c != '\n').is_some() {}
+            }
+            '[' if !in_quotes => depth += 1,
+            ']' if !in_quotes => depth = depth.saturating_sub(1),
+            '@' if !in_quotes && depth == 0 => return Ok((&i[pos..], ())),
+            _ => {}
+        }
+    }
+    Ok((&i[i.len()..], ()))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Problem {
+    MissingClassname,
+    UnknownClass,
+    UnknownKey(String),
+    InvalidValue {
+        key: String,
+        value: String,
+        expected: KeyType,
+    },
+    InvalidChoice {
+        key: String,
+        value: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub entity: usize, // index in the entities lump
+    pub classname: String,
+    pub problem: Problem,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "entity {} ({}): ", self.entity, self.classname)?;
+        match &self.problem {
+            Problem::MissingClassname => write!(f, "no classname"),
+            Problem::UnknownClass => write!(f, "unknown class"),
+            Problem::UnknownKey(key) => write!(f, "unknown key `{}`", key),
+            Problem::InvalidValue {
+                key,
+                value,
+                expected,
+            } => write!(f, "value `{}` of key `{}` is not {}", value, key, expected),
+            Problem::InvalidChoice { key, value } => {
+                write!(f, "value `{}` of key `{}` is not a choice", value, key)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Fgd {
+    classes: Vec<Class>,
+}
+
+impl Fgd {
+    pub fn parse(i: &str) -> Result<Self, SyntaxError> {
+        let (_, classes) = terminated(
+            many0(ws(alt((
+                map(Class::parse, Some),
+                map(unknown_directive, |_| None),
+            )))),
+            ws(context("expected class", eof)),
+        )(i)
+        .map_err(|e| SyntaxError::from_nom(i, e))?;
+        Ok(Self {
+            classes: classes.into_iter().flatten().collect(),
+        })
+    }
+
+    // Mods ship fgd on top of the base game one, later classes override earlier ones
+    pub fn extend(&mut self, other: Self) {
+        self.classes.extend(other.classes);
+    }
+
+    pub fn classes(&self) -> &[Class] {
+        &self.classes
+    }
+
+    pub fn class(&self, name: &str) -> Option<&Class> {
+        self.classes
+            .iter()
+            .rev()
+            .find(|class| class.name.eq_ignore_ascii_case(name))
+    }
+
+    fn with_bases<'a>(&'a self, class: &'a Class, depth: usize, out: &mut Vec<&'a Class>) {
+        if depth > MAX_BASE_DEPTH {
+            return;
+        }
+        for base in &class.bases {
+            if let Some(base) = self.class(base) {
+                self.with_bases(base, depth + 1, out);
+            }
+        }
+        out.push(class);
+    }
+
+    // Base classes go first, so the class itself and later bases override them
+    fn hierarchy<'a>(&'a self, class: &'a Class) -> Vec<&'a Class> {
+        let mut classes = Vec::new();
+        self.with_bases(class, 0, &mut classes);
+        classes
+    }
+
+    pub fn keys<'a>(&'a self, class: &'a Class) -> Vec<&'a Key> {
+        let mut keys: Vec<&Key> = Vec::new();
+        for key in self.hierarchy(class).into_iter().flat_map(|c| &c.keys) {
+            match keys
+                .iter_mut()
+                .find(|k| k.name.eq_ignore_ascii_case(&key.name))
+            {
+                Some(k) => *k = key,
+                None => keys.push(key),
+            }
+        }
+        keys
+    }
+
+    pub fn size(&self, class: &Class) -> Option<(Vec3, Vec3)> {
+        self.hierarchy(class).iter().rev().find_map(|c| c.size)
+    }
+
+    pub fn color(&self, class: &Class) -> Option<(u8, u8, u8)> {
+        self.hierarchy(class).iter().rev().find_map(|c| c.color)
+    }
+
+    pub fn validate(&self, entities: &Entities) -> Vec<Issue> {
+        let mut issues = Vec::new();
+        for (index, entity) in entities.entities().iter().enumerate() {
+            let classname = entity.classname().unwrap_or_default();
+            let issue = |problem| Issue {
+                entity: index,
+                classname: classname.to_string(),
+                problem,
+            };
+            if classname.is_empty() {
+                issues.push(issue(Problem::MissingClassname));
+                continue;
+            }
+            let class = match self.class(classname) {
+                Some(class) if class.ctype != ClassType::Base => class,
+                _ => {
+                    issues.push(issue(Problem::UnknownClass));
+                    continue;
+                }
+            };
+
+            let keys = self.keys(class);
+            for &(key, value) in entity.properties() {
+                let definition = keys.iter().find(|k| k.name.eq_ignore_ascii_case(key));
+                let problem = match definition {
+                    None if BUILTIN_KEYS.iter().any(|x| x.eq_ignore_ascii_case(key))
+                        || COMPILER_KEY_PREFIXES.iter().any(|x| key.starts_with(x)) =>
+                    {
+                        continue
+                    }
+                    None => Problem::UnknownKey(key.to_string()),
+                    // Empty values are the same as missing keys for the game
+                    Some(_) if value.trim().is_empty() => continue,
+                    Some(k) if !k.ktype.accepts(value) => Problem::InvalidValue {
+                        key: key.to_string(),
+                        value: value.to_string(),
+                        expected: k.ktype.clone(),
+                    },
+                    Some(k) if k.ktype == KeyType::Choices && !k.has_choice(value) => {
+                        Problem::InvalidChoice {
+                            key: key.to_string(),
+                            value: value.to_string(),
+                        }
+                    }
+                    Some(_) => continue,
+                };
+                issues.push(issue(problem));
+            }
+        }
+        issues
+    }
+}
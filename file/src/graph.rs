@@ -0,0 +1,292 @@
+use crate::map::{Entities, Entity};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+};
+
+const MULTI_MANAGER_CLASSNAME: &str = "multi_manager";
+const PATH_CLASSNAME_PREFIX: &str = "path_";
+// Keys of multi_manager which aren't targets, the rest are `target` `delay` pairs
+const MULTI_MANAGER_KEYS: [&str; 6] = [
+    "classname",
+    "targetname",
+    "origin",
+    "spawnflags",
+    "angles",
+    "wait",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind {
+    Target,
+    KillTarget,
+    MultiManager,
+    PathCorner, // next corner of a path, or `message` fired on arrival
+}
+
+impl EdgeKind {
+    const fn name(self) -> &'static str {
+        match self {
+            EdgeKind::Target => "target",
+            EdgeKind::KillTarget => "killtarget",
+            EdgeKind::MultiManager => "multi_manager",
+            EdgeKind::PathCorner => "path",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Edge {
+    pub from: usize, // index of the entity
+    pub target: String,
+    pub kind: EdgeKind,
+    pub delay: Option<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    pub entity: usize,
+    pub classname: String,
+    pub targetname: Option<String>,
+}
+
+fn entity_edges(index: usize, entity: &Entity) -> Vec<Edge> {
+    let classname = entity.classname().unwrap_or_default();
+    let edge = |target: &str, kind, delay| Edge {
+        from: index,
+        target: target.to_string(),
+        kind,
+        delay,
+    };
+
+    if classname == MULTI_MANAGER_CLASSNAME {
+        // Keys are unique in the editor, so the same target is written as `name#1`
+        return entity
+            .properties()
+            .iter()
+            .filter(|(key, _)| !MULTI_MANAGER_KEYS.contains(key) && !key.starts_with('_'))
+            .map(|(key, delay)| {
+                let target = key.split('#').next().unwrap_or(key);
+                edge(target, EdgeKind::MultiManager, delay.trim().parse().ok())
+            })
+            .collect();
+    }
+
+    let is_path = classname.starts_with(PATH_CLASSNAME_PREFIX);
+    let delay = entity.get("delay").and_then(|x| x.trim().parse().ok());
+    let mut edges = Vec::new();
+    if let Some(target) = entity.target() {
+        let kind = if is_path {
+            EdgeKind::PathCorner
+        } else {
+            EdgeKind::Target
+        };
+        edges.push(edge(target, kind, delay));
+    }
+    if let Some(target) = entity.get("killtarget") {
+        edges.push(edge(target, EdgeKind::KillTarget, delay));
+    }
+    if let Some(target) = entity.get("message").filter(|_| is_path) {
+        edges.push(edge(target, EdgeKind::PathCorner, None));
+    }
+    edges.retain(|e| !e.target.is_empty());
+    edges
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if c.is_control() => {
+                let _ = write!(escaped, "\\u{:04x}", u32::from(c));
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn json_string(s: &str) -> String {
+    format!("\"{}\"", escape(s))
+}
+
+// Entities triggering each other by targetname, only entities having either side are nodes
+pub struct Graph {
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+    targetnames: HashMap<String, Vec<usize>>,
+    triggered: HashSet<String>,
+}
+
+impl Graph {
+    pub fn new(entities: &Entities) -> Self {
+        let mut edges = Vec::new();
+        let mut targetnames: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut nodes = Vec::new();
+        for (index, entity) in entities.entities().iter().enumerate() {
+            let entity_edges = entity_edges(index, entity);
+            let targetname = entity.targetname().filter(|x| !x.is_empty());
+            if let Some(targetname) = targetname {
+                targetnames
+                    .entry(targetname.to_string())
+                    .or_default()
+                    .push(index);
+            }
+            if targetname.is_some() || !entity_edges.is_empty() {
+                nodes.push(Node {
+                    entity: index,
+                    classname: entity.classname().unwrap_or_default().to_string(),
+                    targetname: targetname.map(str::to_string),
+                });
+            }
+            edges.extend(entity_edges);
+        }
+        let triggered = edges.iter().map(|e| e.target.clone()).collect();
+        Self {
+            nodes,
+            edges,
+            targetnames,
+            triggered,
+        }
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
+    // Entities having the targetname, several ones are triggered at once
+    pub fn targets(&self, name: &str) -> &[usize] {
+        self.targetnames.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    // Edges to targetnames no entity has
+    pub fn dangling(&self) -> impl Iterator<Item = &Edge> {
+        self.edges
+            .iter()
+            .filter(move |e| self.targets(&e.target).is_empty())
+    }
+
+    // Entities with targetname no entity triggers
+    pub fn untriggered(&self) -> impl Iterator<Item = &Node> {
+        self.nodes
+            .iter()
+            .filter(move |node| self.is_untriggered(node))
+    }
+
+    fn is_untriggered(&self, node: &Node) -> bool {
+        node.targetname
+            .as_ref()
+            .is_some_and(|name| !self.triggered.contains(name))
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph entities {\n");
+        for node in &self.nodes {
+            let label = match &node.targetname {
+                Some(targetname) => format!("{}\\n{}", escape(&node.classname), escape(targetname)),
+                None => escape(&node.classname),
+            };
+            let style = if self.is_untriggered(node) {
+                ", color=orange"
+            } else {
+                ""
+            };
+            let _ = writeln!(dot, "    e{} [label=\"{}\"{}];", node.entity, label, style);
+        }
+
+        let mut missing = Vec::new();
+        for edge in &self.edges {
+            let label = match edge.delay {
+                Some(delay) => format!("{} {}s", edge.kind.name(), delay),
+                None => edge.kind.name().to_string(),
+            };
+            let targets = self.targets(&edge.target);
+            if targets.is_empty() {
+                let id = match missing.iter().position(|x| x == &edge.target) {
+                    Some(id) => id,
+                    None => {
+                        missing.push(edge.target.clone());
+                        let _ = writeln!(
+                            dot,
+                            "    m{} [label=\"{}\", color=red, style=dashed];",
+                            missing.len() - 1,
+                            escape(&edge.target)
+                        );
+                        missing.len() - 1
+                    }
+                };
+                let _ = writeln!(dot, "    e{} -> m{} [label=\"{}\"];", edge.from, id, label);
+            }
+            for target in targets {
+                let _ = writeln!(
+                    dot,
+                    "    e{} -> e{} [label=\"{}\"];",
+                    edge.from, target, label
+                );
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    pub fn to_json(&self) -> String {
+        let nodes: Vec<String> = self
+            .nodes
+            .iter()
+            .map(|node| {
+                format!(
+                    "{{\"entity\":{},\"classname\":{},\"targetname\":{},\"triggered\":{}}}",
+                    node.entity,
+                    json_string(&node.classname),
+                    node.targetname
+                        .as_deref()
+                        .map_or_else(|| "null".to_string(), json_string),
+                    !self.is_untriggered(node)
+                )
+            })
+            .collect();
+        let edges: Vec<String> = self
+            .edges
+            .iter()
+            .map(|edge| {
+                let targets: Vec<String> = self
+                    .targets(&edge.target)
+                    .iter()
+                    .map(usize::to_string)
+                    .collect();
+                format!(
+                    "{{\"from\":{},\"target\":{},\"to\":[{}],\"kind\":{},\"delay\":{}}}",
+                    edge.from,
+                    json_string(&edge.target),
+                    targets.join(","),
+                    json_string(edge.kind.name()),
+                    edge.delay
+                        .filter(|x| x.is_finite())
+                        .map_or_else(|| "null".to_string(), |x| x.to_string())
+                )
+            })
+            .collect();
+        let mut dangling: Vec<String> = Vec::new();
+        for name in self.dangling().map(|e| json_string(&e.target)) {
+            if !dangling.contains(&name) {
+                dangling.push(name);
+            }
+        }
+        let untriggered: Vec<String> = self.untriggered().map(|x| x.entity.to_string()).collect();
+
+        format!(
+            "{{\"nodes\":[{}],\"edges\":[{}],\"dangling\":[{}],\"untriggered\":[{}]}}\n",
+            nodes.join(","),
+            edges.join(","),
+            dangling.join(","),
+            untriggered.join(",")
+        )
+    }
+}
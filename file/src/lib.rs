@@ -1,5 +1,10 @@
 pub mod bsp;
 pub mod cubemap;
+pub mod detail;
+pub mod fgd;
+pub mod graph;
 pub mod map;
 pub mod miptex;
+pub mod text;
+pub mod vfs;
 pub mod wad;
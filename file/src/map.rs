@@ -1,59 +1,375 @@
+use crate::bsp::lumps::Vec3;
 use nom::{
-    bytes::complete::take_until,
-    character::complete::{char as character, multispace0},
+    branch::alt,
+    bytes::complete::{tag, take_till},
+    character::complete::{char as character, multispace1, not_line_ending},
+    combinator::{cut, eof, map},
+    error::{context, VerboseErrorKind},
     multi::many0,
-    sequence::{delimited, separated_pair},
+    sequence::{pair, preceded, terminated},
 };
-use std::collections::HashMap;
+use std::{fmt, str::FromStr};
 
 type Input<'a> = &'a str;
 type ParseError<'a> = nom::error::VerboseError<Input<'a>>;
 type ParseResult<'a, O> = nom::IResult<Input<'a>, O, ParseError<'a>>;
-type OnlyResult<'a, O> = Result<O, nom::Err<ParseError<'a>>>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError {
+    line: usize,
+    column: usize,
+    message: &'static str,
+}
+
+impl SyntaxError {
+    fn new(input: &str, position: &str, message: &'static str) -> Self {
+        let consumed = &input[..input.len() - position.len()];
+        let line_start = consumed.rfind('\n').map_or(0, |x| x + 1);
+        Self {
+            line: consumed.matches('\n').count() + 1,
+            column: consumed[line_start..].chars().count() + 1,
+            message,
+        }
+    }
+
+    // The innermost context is the most precise one, e.g. string inside of entity
+    pub(crate) fn from_nom(input: &str, err: nom::Err<ParseError>) -> Self {
+        match err {
+            nom::Err::Error(e) | nom::Err::Failure(e) => {
+                let (position, message) = e
+                    .errors
+                    .iter()
+                    .find_map(|(i, kind)| match kind {
+                        VerboseErrorKind::Context(message) => Some((*i, *message)),
+                        _ => None,
+                    })
+                    .unwrap_or((e.errors.first().map_or("", |x| x.0), "invalid syntax"));
+                Self::new(input, position, message)
+            }
+            nom::Err::Incomplete(_) => Self::new(input, "", "unexpected end"),
+        }
+    }
+
+    pub const fn line(&self) -> usize {
+        self.line
+    }
+
+    pub const fn column(&self) -> usize {
+        self.column
+    }
+
+    pub const fn message(&self) -> &str {
+        self.message
+    }
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {}",
+            self.message, self.line, self.column
+        )
+    }
+}
+
+impl std::error::Error for SyntaxError {}
+
+// Whitespaces (CRLF too) and `//` comments some tools leave in .ent files
+fn space(i: &str) -> ParseResult<'_, ()> {
+    map(
+        many0(alt((multispace1, preceded(tag("//"), not_line_ending)))),
+        |_| (),
+    )(i)
+}
+
+// Strings can't contain quotes and compilers don't allow multiline values
+fn quoted(i: &str) -> ParseResult<'_, &str> {
+    let (string_i, _) = character('"')(i)?;
+    terminated(
+        take_till(|c| c == '"' || c == '\n' || c == '\r'),
+        character('"'),
+    )(string_i)
+    .map_err(|_: nom::Err<ParseError>| {
+        nom::Err::Failure(ParseError {
+            errors: vec![(i, VerboseErrorKind::Context("unterminated string"))],
+        })
+    })
+}
+
+// Value of a known key that can't be parsed, missing keys aren't errors
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValueError {
+    key: &'static str,
+    value: String,
+}
+
+impl ValueError {
+    pub const fn key(&self) -> &str {
+        self.key
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
+
+impl fmt::Display for ValueError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid value `{}` of key `{}`", self.value, self.key)
+    }
+}
+
+impl std::error::Error for ValueError {}
+
+type ValueResult<T> = Result<Option<T>, ValueError>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelRef<'a> {
+    Brush(usize), // `*N`, index of the model in the bsp
+    File(&'a str),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderMode {
+    Normal,
+    Color,
+    Texture,
+    Glow,
+    Solid,
+    Additive,
+}
+
+impl RenderMode {
+    fn from_index(index: u8) -> Option<Self> {
+        Some(match index {
+            0 => Self::Normal,
+            1 => Self::Color,
+            2 => Self::Texture,
+            3 => Self::Glow,
+            4 => Self::Solid,
+            5 => Self::Additive,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Light {
+    pub color: Vec3,
+    pub intensity: f32,
+}
+
+const WORLDSPAWN_CLASSNAME: &str = "worldspawn";
+const ANGLE_UP: f32 = -1.0;
+const ANGLE_DOWN: f32 = -2.0;
+const DEFAULT_LIGHT_INTENSITY: f32 = 255.0;
+
+// Wads are looked up by the file name in the game directories, whatever the path is
+pub fn wad_name(path: &str) -> &str {
+    path.rsplit(['\\', '/']).next().unwrap_or(path).trim()
+}
+
+fn parse_numbers<T: FromStr>(s: &str) -> Option<Vec<T>> {
+    s.split_whitespace().map(|x| x.parse().ok()).collect()
+}
+
+// Vectors are written as `x y z` by editors and in game definitions
+pub fn parse_vec3(s: &str) -> Option<Vec3> {
+    match parse_numbers(s)?[..] {
+        [x, y, z] => Some((x, y, z)),
+        _ => None,
+    }
+}
 
 #[derive(Debug)]
 pub struct Entity<'a> {
-    properties: HashMap<&'a str, &'a str>,
+    properties: Vec<(&'a str, &'a str)>, // in order of the lump
 }
 
 impl<'a> Entity<'a> {
-    fn entry(i: &str) -> ParseResult<(&str, &str)> {
-        separated_pair(
-            delimited(character('"'), take_until("\""), character('"')),
-            multispace0,
-            delimited(character('"'), take_until("\""), character('"')),
+    fn entry(i: &str) -> ParseResult<'_, (&str, &str)> {
+        pair(
+            quoted,
+            cut(preceded(space, context("expected value", quoted))),
         )(i)
     }
 
-    fn entries(i: &str) -> ParseResult<Vec<(&str, &str)>> {
-        many0(delimited(multispace0, Self::entry, multispace0))(i)
-    }
-
     fn parse(i: &'a str) -> ParseResult<'a, Self> {
-        let (i, properties) = delimited(
-            character('{'),
-            delimited(multispace0, Self::entries, multispace0),
-            character('}'),
+        let (i, properties) = context(
+            "unterminated entity",
+            preceded(
+                character('{'),
+                cut(terminated(
+                    many0(preceded(space, Self::entry)),
+                    preceded(space, character('}')),
+                )),
+            ),
         )(i)?;
-        let properties = properties.into_iter().collect();
         Ok((i, Self { properties }))
     }
 
-    pub fn properties(&self) -> &HashMap<&str, &str> {
+    pub fn properties(&self) -> &[(&'a str, &'a str)] {
         &self.properties
     }
+
+    // The engine applies keys in order, so the last duplicate wins
+    pub fn get(&self, key: &str) -> Option<&'a str> {
+        self.get_all(key).last()
+    }
+
+    pub fn get_all<'b>(&'b self, key: &'b str) -> impl Iterator<Item = &'a str> + 'b {
+        self.properties
+            .iter()
+            .filter(move |(k, _)| *k == key)
+            .map(|&(_, v)| v)
+    }
+
+    fn parse_value<T, F: FnOnce(&str) -> Option<T>>(
+        &self,
+        key: &'static str,
+        parse: F,
+    ) -> ValueResult<T> {
+        self.get(key)
+            .map(|value| {
+                parse(value.trim()).ok_or_else(|| ValueError {
+                    key,
+                    value: value.to_string(),
+                })
+            })
+            .transpose()
+    }
+
+    pub fn classname(&self) -> Option<&'a str> {
+        self.get("classname")
+    }
+
+    pub fn targetname(&self) -> Option<&'a str> {
+        self.get("targetname")
+    }
+
+    pub fn target(&self) -> Option<&'a str> {
+        self.get("target")
+    }
+
+    // Worldspawn lists wads with paths like `\half-life\valve\halflife.wad;\half-life\valve\decals.wad`
+    pub fn wads(&self) -> impl Iterator<Item = &'a str> {
+        self.get("wad")
+            .into_iter()
+            .flat_map(|wads| wads.split(';'))
+            .filter(|path| !path.trim().is_empty())
+    }
+
+    pub fn origin(&self) -> ValueResult<Vec3> {
+        self.parse_value("origin", parse_vec3)
+    }
+
+    // Pitch, yaw and roll; `angle` sets yaw only, -1 and -2 mean up and down
+    pub fn angles(&self) -> ValueResult<Vec3> {
+        if self.get("angles").is_some() {
+            return self.parse_value("angles", parse_vec3);
+        }
+        self.parse_value("angle", |s| {
+            s.parse().ok().map(|angle| match angle {
+                ANGLE_UP => (-90.0, 0.0, 0.0),
+                ANGLE_DOWN => (90.0, 0.0, 0.0),
+                yaw => (0.0, yaw, 0.0),
+            })
+        })
+    }
+
+    pub fn model(&self) -> ValueResult<ModelRef<'a>> {
+        match self.get("model") {
+            Some(model) if model.starts_with('*') => {
+                self.parse_value("model", |s| s[1..].parse().ok().map(ModelRef::Brush))
+            }
+            model => Ok(model.map(ModelRef::File)),
+        }
+    }
+
+    pub fn rendermode(&self) -> ValueResult<RenderMode> {
+        self.parse_value("rendermode", |s| {
+            s.parse().ok().and_then(RenderMode::from_index)
+        })
+    }
+
+    pub fn renderamt(&self) -> ValueResult<u8> {
+        self.parse_value("renderamt", |s| s.parse().ok())
+    }
+
+    pub fn rendercolor(&self) -> ValueResult<(u8, u8, u8)> {
+        self.parse_value("rendercolor", |s| match parse_numbers(s)?[..] {
+            [r, g, b] => Some((r, g, b)),
+            _ => None,
+        })
+    }
+
+    pub fn spawnflags(&self) -> ValueResult<u32> {
+        self.parse_value("spawnflags", |s| s.parse().ok())
+    }
+
+    // `_light` is `r g b intensity` (intensity is optional) or just brightness like Quake `light`
+    pub fn light(&self) -> ValueResult<Light> {
+        let parse = |s: &str| match parse_numbers(s)?[..] {
+            [r, g, b, intensity] => Some(Light {
+                color: (r, g, b),
+                intensity,
+            }),
+            [r, g, b] => Some(Light {
+                color: (r, g, b),
+                intensity: DEFAULT_LIGHT_INTENSITY,
+            }),
+            [brightness] => Some(Light {
+                color: (brightness, brightness, brightness),
+                intensity: DEFAULT_LIGHT_INTENSITY,
+            }),
+            _ => None,
+        };
+        if self.get("_light").is_some() {
+            self.parse_value("_light", parse)
+        } else {
+            self.parse_value("light", parse)
+        }
+    }
+}
+
+// Canonical form the compilers write, one key per line
+impl fmt::Display for Entity<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{{")?;
+        for (key, value) in &self.properties {
+            writeln!(f, "\"{}\" \"{}\"", key, value)?;
+        }
+        writeln!(f, "}}")
+    }
 }
 
 #[derive(Debug)]
 pub struct Entities<'a>(Vec<Entity<'a>>);
 
 impl<'a> Entities<'a> {
-    pub fn parse(i: &'a str) -> OnlyResult<Self> {
-        let (_, ents) = many0(delimited(multispace0, Entity::parse, multispace0))(i)?;
+    pub fn parse(i: &'a str) -> Result<Self, SyntaxError> {
+        let (_, ents) = terminated(
+            many0(preceded(space, Entity::parse)),
+            preceded(space, context("expected entity", eof)),
+        )(i)
+        .map_err(|e| SyntaxError::from_nom(i, e))?;
         Ok(Self(ents))
     }
 
-    pub fn entities(&self) -> &[Entity] {
+    pub fn entities(&self) -> &[Entity<'_>] {
         &self.0
     }
+
+    pub fn worldspawn(&self) -> Option<&Entity<'a>> {
+        self.0
+            .iter()
+            .find(|e| e.classname() == Some(WORLDSPAWN_CLASSNAME))
+    }
+}
+
+impl fmt::Display for Entities<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.iter().try_for_each(|entity| write!(f, "{}", entity))
+    }
 }
@@ -0,0 +1,231 @@
+use super::{MIP_NUM, NAME_LEN};
+use std::{
+    collections::HashMap,
+    io::{Error as IOError, ErrorKind, Result as IOResult},
+};
+
+const PALETTE_COLORS: usize = 256;
+const TRANSPARENT_INDEX: u8 = 255;
+const TRANSPARENT_COLOR: [u8; 3] = [0, 0, 255];
+const HEADER_SIZE: usize = NAME_LEN + 4 * 2 + 4 * MIP_NUM;
+const SIZE_ALIGNMENT: u32 = 16; // so every mip level has whole texels
+
+type Color = [u8; 3];
+
+struct ColorBox {
+    colors: Vec<(Color, usize)>, // with number of pixels
+}
+
+impl ColorBox {
+    fn range(&self, channel: usize) -> u8 {
+        let (min, max) = self
+            .colors
+            .iter()
+            .fold((u8::MAX, u8::MIN), |(min, max), (c, _)| {
+                (min.min(c[channel]), max.max(c[channel]))
+            });
+        max.saturating_sub(min)
+    }
+
+    fn widest_channel(&self) -> (usize, u8) {
+        (0..3)
+            .map(|channel| (channel, self.range(channel)))
+            .max_by_key(|&(_, range)| range)
+            .unwrap()
+    }
+
+    // Split at the median pixel along the widest channel
+    fn split(mut self) -> (Self, Self) {
+        let (channel, _) = self.widest_channel();
+        self.colors.sort_by_key(|(c, _)| c[channel]);
+        let total: usize = self.colors.iter().map(|(_, n)| n).sum();
+        let mut acc = 0;
+        let mut at = self.colors.len() - 1;
+        for (i, (_, n)) in self.colors.iter().enumerate() {
+            acc += n;
+            if acc * 2 >= total {
+                at = i + 1;
+                break;
+            }
+        }
+        let at = at.clamp(1, self.colors.len() - 1);
+        let rest = self.colors.split_off(at);
+        (self, Self { colors: rest })
+    }
+
+    fn average(&self) -> Color {
+        let total: usize = self.colors.iter().map(|(_, n)| n).sum();
+        let mut sum = [0usize; 3];
+        for (c, n) in &self.colors {
+            for channel in 0..3 {
+                sum[channel] += c[channel] as usize * n;
+            }
+        }
+        let total = total.max(1);
+        [
+            ((sum[0] + total / 2) / total) as u8,
+            ((sum[1] + total / 2) / total) as u8,
+            ((sum[2] + total / 2) / total) as u8,
+        ]
+    }
+}
+
+// Median cut quantization
+fn build_palette(pixels: &[Color], max_colors: usize) -> Vec<Color> {
+    let mut histogram = HashMap::new();
+    for &c in pixels {
+        *histogram.entry(c).or_insert(0) += 1;
+    }
+    let mut colors: Vec<_> = histogram.into_iter().collect();
+    colors.sort_unstable();
+    if colors.is_empty() {
+        return vec![];
+    }
+
+    let mut boxes = vec![ColorBox { colors }];
+    while boxes.len() < max_colors {
+        let splittable = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.colors.len() > 1)
+            .max_by_key(|(_, b)| b.widest_channel().1)
+            .map(|(i, _)| i);
+        match splittable {
+            Some(i) => {
+                let (a, b) = boxes.swap_remove(i).split();
+                boxes.push(a);
+                boxes.push(b);
+            }
+            None => break,
+        }
+    }
+    boxes.iter().map(ColorBox::average).collect()
+}
+
+fn nearest(palette: &[Color], c: Color) -> u8 {
+    let distance = |p: &Color| {
+        (0..3)
+            .map(|i| (p[i] as i32 - c[i] as i32).pow(2))
+            .sum::<i32>()
+    };
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, p)| distance(p))
+        .map_or(0, |(i, _)| i as u8)
+}
+
+// Box filter, transparent texels (if there're any) are left out of the color average
+fn downscale(rgba: &[u8], width: usize, height: usize, transparent: bool) -> Vec<u8> {
+    let (w, h) = (width / 2, height / 2);
+    let mut out = Vec::with_capacity(w * h * 4);
+    for y in 0..h {
+        for x in 0..w {
+            let texels = [(0, 0), (1, 0), (0, 1), (1, 1)]
+                .iter()
+                .map(|(dx, dy)| {
+                    let i = ((y * 2 + dy) * width + x * 2 + dx) * 4;
+                    &rgba[i..i + 4]
+                })
+                .collect::<Vec<_>>();
+            let opaque = texels
+                .iter()
+                .filter(|t| !transparent || t[3] >= 128)
+                .collect::<Vec<_>>();
+            if opaque.len() * 2 < texels.len() {
+                out.extend_from_slice(&[0, 0, 0, 0]);
+            } else {
+                for channel in 0..3 {
+                    let sum: usize = opaque.iter().map(|t| t[channel] as usize).sum();
+                    out.push(((sum + opaque.len() / 2) / opaque.len()) as u8);
+                }
+                out.push(255);
+            }
+        }
+    }
+    out
+}
+
+// Encodes RGBA image as miptex with embedded palette, as it's stored in wad3 and bsp.
+// Textures named `{...` get transparent pixels (alpha < 128) mapped to index 255 (blue).
+pub fn encode(name: &str, width: u32, height: u32, rgba: &[u8]) -> IOResult<Vec<u8>> {
+    let invalid = |msg: String| IOError::new(ErrorKind::InvalidInput, msg);
+    if name.is_empty() || name.len() >= NAME_LEN || !name.is_ascii() {
+        return Err(invalid(format!("Invalid texture name `{}`", name)));
+    }
+    if width == 0 || height == 0 || width % SIZE_ALIGNMENT != 0 || height % SIZE_ALIGNMENT != 0 {
+        return Err(invalid(format!(
+            "Texture size must be multiple of {}, got {}x{}",
+            SIZE_ALIGNMENT, width, height
+        )));
+    }
+    let (width, height) = (width as usize, height as usize);
+    if rgba.len() != width * height * 4 {
+        return Err(invalid(format!(
+            "Expected {} bytes of RGBA, got {}",
+            width * height * 4,
+            rgba.len()
+        )));
+    }
+
+    let transparent = name.starts_with('{');
+    let is_transparent = |texel: &[u8]| transparent && texel[3] < 128;
+
+    let mut mip_levels = vec![rgba.to_vec()];
+    for i in 1..MIP_NUM {
+        let prev = &mip_levels[i - 1];
+        let next = downscale(prev, width >> (i - 1), height >> (i - 1), transparent);
+        mip_levels.push(next);
+    }
+
+    let opaque_colors = rgba
+        .chunks(4)
+        .filter(|texel| !is_transparent(texel))
+        .map(|texel| [texel[0], texel[1], texel[2]])
+        .collect::<Vec<_>>();
+    let max_colors = if transparent {
+        PALETTE_COLORS - 1
+    } else {
+        PALETTE_COLORS
+    };
+    let mut palette = build_palette(&opaque_colors, max_colors);
+    palette.resize(PALETTE_COLORS, [0; 3]);
+    if transparent {
+        palette[TRANSPARENT_INDEX as usize] = TRANSPARENT_COLOR;
+    }
+    let lookup_palette = &palette[..max_colors];
+
+    let mut cache = HashMap::new();
+    let mut indices = |level: &[u8]| {
+        level
+            .chunks(4)
+            .map(|texel| {
+                if is_transparent(texel) {
+                    TRANSPARENT_INDEX
+                } else {
+                    let c = [texel[0], texel[1], texel[2]];
+                    *cache.entry(c).or_insert_with(|| nearest(lookup_palette, c))
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let mut out = Vec::with_capacity(HEADER_SIZE + width * height * 85 / 64 + 2 + 256 * 3 + 2);
+    let mut name_bytes = [0; NAME_LEN];
+    name_bytes[..name.len()].copy_from_slice(name.as_bytes());
+    out.extend_from_slice(&name_bytes);
+    out.extend_from_slice(&(width as u32).to_le_bytes());
+    out.extend_from_slice(&(height as u32).to_le_bytes());
+    let mut offset = HEADER_SIZE;
+    for i in 0..MIP_NUM {
+        out.extend_from_slice(&(offset as u32).to_le_bytes());
+        offset += (width * height) >> (2 * i);
+    }
+    for level in &mip_levels {
+        out.extend(indices(level));
+    }
+    out.extend_from_slice(&(PALETTE_COLORS as u16).to_le_bytes());
+    palette.iter().for_each(|c| out.extend_from_slice(c));
+    out.extend_from_slice(&[0; 2]); // padding to 4 bytes
+    Ok(out)
+}
@@ -0,0 +1,141 @@
+pub mod encoder;
+
+use crate::text::{cstr_bytes, decode};
+use nom::{
+    bytes::complete::take, combinator::map, multi::count, number::complete::le_u32, sequence::tuple,
+};
+use std::{borrow::Cow, iter::once};
+
+const MIP_NUM: usize = 4;
+const NAME_LEN: usize = 16;
+const COLOR_TABLE_SIZE: usize = 256 * 3;
+
+type Input<'a> = &'a [u8];
+type ParseResult<'a, O> = nom::IResult<Input<'a>, O, ParseError<'a>>;
+type ParseError<'a> = nom::error::VerboseError<Input<'a>>;
+
+fn take_cstr(i: &[u8], size: usize) -> ParseResult<'_, &[u8]> {
+    map(take(size), cstr_bytes)(i)
+}
+
+enum ColorTable<'a> {
+    Embedded,
+    External(Option<&'a [u8]>),
+}
+
+pub struct MipTexture<'a> {
+    name: Cow<'a, str>,
+    raw_name: &'a [u8],
+    width: u32,
+    height: u32,
+    color_indices: Option<[&'a [u8]; MIP_NUM]>,
+    color_table: Option<&'a [u8]>,
+}
+
+impl<'a> MipTexture<'a> {
+    pub fn parse(file: &'a [u8]) -> Result<MipTexture<'a>, nom::Err<ParseError<'a>>> {
+        Self::parse_impl(file, ColorTable::Embedded)
+    }
+
+    // Quake miptex has no embedded color table, texture is empty if palette isn't given
+    pub fn parse_with_palette(
+        file: &'a [u8],
+        palette: Option<&'a [u8]>,
+    ) -> Result<MipTexture<'a>, nom::Err<ParseError<'a>>> {
+        Self::parse_impl(file, ColorTable::External(palette))
+    }
+
+    fn parse_impl(
+        file: &'a [u8],
+        color_table: ColorTable<'a>,
+    ) -> Result<MipTexture<'a>, nom::Err<ParseError<'a>>> {
+        let (_, (raw_name, width, height, offsets)) = tuple((
+            { |i| take_cstr(i, NAME_LEN) },
+            le_u32,
+            le_u32,
+            count(map(le_u32, |x| x as usize), MIP_NUM),
+        ))(file)?;
+
+        let (color_indices, color_table) = if offsets.contains(&0) {
+            (None, None)
+        } else {
+            let mut color_indices: [&[u8]; MIP_NUM] = [&[]; MIP_NUM];
+            for i in 0..MIP_NUM {
+                let mip_offset = offsets[i];
+                let (mip_i, _) = take(mip_offset)(file)?;
+                let (_, mip_indices) =
+                    take((width as usize * height as usize) / (1 << (2 * i)))(mip_i)?;
+                color_indices[i] = mip_indices;
+            }
+
+            let color_table = match color_table {
+                ColorTable::External(palette) => palette,
+                ColorTable::Embedded => {
+                    let color_table_offset = offsets[MIP_NUM - 1]
+                        + (width as usize * height as usize) / (1 << (2 * (MIP_NUM - 1)))
+                        + 2; // 2 is gap
+                    let (color_table_i, _) = take(color_table_offset)(file)?;
+                    let (_, color_table) = take(COLOR_TABLE_SIZE)(color_table_i)?;
+                    Some(color_table)
+                }
+            };
+            (Some(color_indices), color_table)
+        };
+
+        Ok(MipTexture {
+            name: decode(raw_name),
+            raw_name,
+            width,
+            height,
+            color_indices,
+            color_table,
+        })
+    }
+
+    pub const fn layers() -> usize {
+        MIP_NUM
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // Name bytes as stored in the header, `name` is them decoded as UTF-8 or Windows-1252
+    pub const fn raw_name(&self) -> &'a [u8] {
+        self.raw_name
+    }
+
+    pub const fn main_width(&self) -> u32 {
+        self.width
+    }
+
+    pub const fn main_height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.color_table.is_none() || self.color_indices.is_none()
+    }
+
+    pub fn pixels(&self, mip_level: usize) -> Option<Vec<u8>> {
+        Some(indices_to_rgba(
+            self.color_indices?[mip_level],
+            self.color_table?,
+        ))
+    }
+}
+
+// Blue-ish colors are transparent, which is how `{` textures are masked
+pub(crate) fn indices_to_rgba(indices: &[u8], color_table: &[u8]) -> Vec<u8> {
+    indices
+        .iter()
+        .map(|&i| i as usize)
+        .flat_map(|i| {
+            let r = color_table.get(3 * i).copied().unwrap_or_default();
+            let g = color_table.get(3 * i + 1).copied().unwrap_or_default();
+            let b = color_table.get(3 * i + 2).copied().unwrap_or_default();
+            let a = if r < 30 && g < 30 && b > 125 { 0 } else { 255 };
+            once(r).chain(once(g)).chain(once(b)).chain(once(a))
+        })
+        .collect()
+}
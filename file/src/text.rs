@@ -0,0 +1,70 @@
+use std::borrow::Cow;
+
+// Windows-1252 characters of 0x80..0xA0, undefined ones are kept as C1 controls to round-trip
+const HIGH_CHARS: [char; 32] = [
+    '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008D}', '\u{017D}', '\u{008F}',
+    '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{009D}', '\u{017E}', '\u{0178}',
+];
+const UNMAPPED_BYTE: u8 = b'?';
+
+// Game content is authored on Windows, but newer tools write UTF-8, so it's tried first
+pub fn decode(bytes: &[u8]) -> Cow<'_, str> {
+    match std::str::from_utf8(bytes) {
+        Ok(s) => Cow::Borrowed(s),
+        Err(_) => Cow::Owned(decode_windows_1252(bytes)),
+    }
+}
+
+pub fn decode_windows_1252(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&b| match b {
+            0x80..=0x9F => HIGH_CHARS[usize::from(b - 0x80)],
+            b => char::from(b),
+        })
+        .collect()
+}
+
+// Characters missing in Windows-1252 become `?`
+pub fn encode_windows_1252(s: &str) -> Vec<u8> {
+    s.chars()
+        .map(|c| match u32::from(c) {
+            x @ (0..=0x7F | 0xA0..=0xFF) => x as u8,
+            _ => HIGH_CHARS
+                .iter()
+                .position(|&x| x == c)
+                .map_or(UNMAPPED_BYTE, |i| 0x80 + i as u8),
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Windows1252,
+}
+
+// The same detection as `decode` does
+pub fn detect(bytes: &[u8]) -> Encoding {
+    match std::str::from_utf8(bytes) {
+        Ok(_) => Encoding::Utf8,
+        Err(_) => Encoding::Windows1252,
+    }
+}
+
+pub fn encode(s: &str, encoding: Encoding) -> Vec<u8> {
+    match encoding {
+        Encoding::Utf8 => s.as_bytes().to_vec(),
+        Encoding::Windows1252 => encode_windows_1252(s),
+    }
+}
+
+// Bytes of null terminated string of fixed size field, all bytes if there's no terminator
+pub(crate) fn cstr_bytes(field: &[u8]) -> &[u8] {
+    field
+        .iter()
+        .position(|&b| b == 0)
+        .map_or(field, |end| &field[..end])
+}
@@ -0,0 +1,164 @@
+use std::{
+    fs::{read, read_dir},
+    io::{Error as IOError, ErrorKind, Result as IOResult},
+    path::{Component, Path, PathBuf},
+};
+
+pub const BASE_GAME: &str = "valve";
+// Same order as the engine searches them for each game directory
+const GAME_DIR_SUFFIXES: [&str; 4] = ["_addon", "_hd", "", "_downloads"];
+
+pub const MAPS_DIR: &str = "maps";
+pub const GFX_DIR: &str = "gfx";
+pub const SKY_DIR: &str = "gfx/env";
+pub const MODELS_DIR: &str = "models";
+pub const SPRITES_DIR: &str = "sprites";
+
+#[derive(Debug, Default)]
+pub struct FileSystem {
+    search_paths: Vec<PathBuf>,
+}
+
+impl FileSystem {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Mounts Half-Life install the way the engine does: mod directories go first, `valve` last
+    pub fn with_game<P: AsRef<Path>, S: AsRef<str>>(base_dir: P, game: S) -> Self {
+        let mut fs = Self::new();
+        fs.mount_game(base_dir, game);
+        fs
+    }
+
+    pub fn mount<P: Into<PathBuf>>(&mut self, dir: P) {
+        let dir = dir.into();
+        if dir.is_dir() && !self.search_paths.contains(&dir) {
+            self.search_paths.push(dir);
+        }
+    }
+
+    pub fn mount_game<P: AsRef<Path>, S: AsRef<str>>(&mut self, base_dir: P, game: S) {
+        let base_dir = base_dir.as_ref();
+        let game = game.as_ref();
+        let games = if game.eq_ignore_ascii_case(BASE_GAME) {
+            vec![BASE_GAME]
+        } else {
+            vec![game, BASE_GAME]
+        };
+        for game in games {
+            for suffix in &GAME_DIR_SUFFIXES {
+                let dir = format!("{}{}", game, suffix);
+                if let Some(dir) = find_case_insensitive(base_dir, Path::new(&dir)) {
+                    self.mount(dir);
+                }
+            }
+        }
+    }
+
+    pub fn search_paths(&self) -> &[PathBuf] {
+        &self.search_paths
+    }
+
+    pub fn resolve<P: AsRef<Path>>(&self, relative: P) -> Option<PathBuf> {
+        let relative = relative.as_ref();
+        self.search_paths
+            .iter()
+            .find_map(|dir| find_case_insensitive(dir, relative))
+    }
+
+    pub fn read<P: AsRef<Path>>(&self, relative: P) -> IOResult<Vec<u8>> {
+        let relative = relative.as_ref();
+        let path = self.resolve(relative).ok_or_else(|| {
+            IOError::new(
+                ErrorKind::NotFound,
+                format!("{:?} not found in search paths", relative),
+            )
+        })?;
+        read(path)
+    }
+
+    // Paths given by user are used as is if they exist, otherwise they're looked up by name
+    pub fn locate_map<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        locate(path.as_ref(), |name| self.find_map(name))
+    }
+
+    pub fn locate_wad<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        locate(path.as_ref(), |name| self.find_wad(name))
+    }
+
+    pub fn find_map<S: AsRef<str>>(&self, name: S) -> Option<PathBuf> {
+        self.resolve(Path::new(MAPS_DIR).join(with_extension(name.as_ref(), "bsp")))
+    }
+
+    pub fn find_wad<S: AsRef<str>>(&self, name: S) -> Option<PathBuf> {
+        self.resolve(with_extension(name.as_ref(), "wad"))
+    }
+
+    pub fn find_sky<S: AsRef<str>>(&self, file_name: S) -> Option<PathBuf> {
+        self.resolve(Path::new(SKY_DIR).join(file_name.as_ref()))
+    }
+
+    pub fn find_model<S: AsRef<str>>(&self, name: S) -> Option<PathBuf> {
+        self.resolve(model_relative_path(name.as_ref(), MODELS_DIR, "mdl"))
+    }
+
+    pub fn find_sprite<S: AsRef<str>>(&self, name: S) -> Option<PathBuf> {
+        self.resolve(model_relative_path(name.as_ref(), SPRITES_DIR, "spr"))
+    }
+}
+
+fn locate<F: Fn(&str) -> Option<PathBuf>>(path: &Path, find: F) -> PathBuf {
+    if path.exists() {
+        return path.to_path_buf();
+    }
+    path.to_str()
+        .and_then(find)
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+fn with_extension(name: &str, extension: &str) -> String {
+    if Path::new(name).extension().is_some() {
+        name.to_string()
+    } else {
+        format!("{}.{}", name, extension)
+    }
+}
+
+// Entities reference models both as `models/x.mdl` and as bare `x.mdl`
+fn model_relative_path(name: &str, dir: &str, extension: &str) -> PathBuf {
+    let name = with_extension(name, extension);
+    let path = Path::new(&name);
+    if path.parent().map_or(true, |p| p.as_os_str().is_empty()) {
+        Path::new(dir).join(path)
+    } else {
+        path.to_path_buf()
+    }
+}
+
+// Game content is authored on Windows, so names are matched ignoring case like the engine does
+fn find_case_insensitive(dir: &Path, relative: &Path) -> Option<PathBuf> {
+    let exact = dir.join(relative);
+    if exact.exists() {
+        return Some(exact);
+    }
+
+    let mut path = dir.to_path_buf();
+    for component in relative.components() {
+        match component {
+            Component::Normal(name) => {
+                let name = name.to_str()?;
+                let entry = read_dir(&path).ok()?.flatten().find(|entry| {
+                    entry
+                        .file_name()
+                        .to_str()
+                        .is_some_and(|x| x.eq_ignore_ascii_case(name))
+                })?;
+                path.push(entry.file_name());
+            }
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    Some(path)
+}
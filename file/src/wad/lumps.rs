@@ -0,0 +1,176 @@
+use crate::miptex::indices_to_rgba;
+use nom::{
+    bytes::complete::take,
+    combinator::map,
+    multi::count,
+    number::complete::{le_u16, le_u32},
+    sequence::tuple,
+};
+
+const PALETTE_SIZE: usize = 256 * 3;
+const FONT_CHARS_NUM: usize = 256;
+
+type Input<'a> = &'a [u8];
+type ParseError<'a> = nom::error::VerboseError<Input<'a>>;
+type ParseResult<'a, O> = nom::IResult<Input<'a>, O, ParseError<'a>>;
+type OnlyResult<'a, O> = Result<O, nom::Err<ParseError<'a>>>;
+
+fn parse_color_table(i: &[u8]) -> ParseResult<'_, &[u8]> {
+    let (i, colors_num) = map(le_u16, |x| x as usize)(i)?;
+    take(colors_num * 3)(i)
+}
+
+pub struct Palette<'a> {
+    colors: &'a [u8],
+}
+
+impl<'a> Palette<'a> {
+    pub fn parse(data: &'a [u8]) -> OnlyResult<'a, Self> {
+        let (_, colors) = take(PALETTE_SIZE)(data)?;
+        Ok(Self { colors })
+    }
+
+    pub const fn colors(&self) -> &[u8] {
+        self.colors
+    }
+
+    // 16x16 image, one texel per color
+    pub fn pixels(&self) -> Vec<u8> {
+        let indices: Vec<u8> = (0..=255).collect();
+        indices_to_rgba(&indices, self.colors)
+    }
+}
+
+pub struct QPic<'a> {
+    width: u32,
+    height: u32,
+    color_indices: &'a [u8],
+    color_table: Option<&'a [u8]>,
+}
+
+impl<'a> QPic<'a> {
+    pub fn parse(data: &'a [u8]) -> OnlyResult<'a, Self> {
+        let (i, qpic) = Self::parse_indices(data)?;
+        let (_, color_table) = parse_color_table(i)?;
+        Ok(Self {
+            color_table: Some(color_table),
+            ..qpic
+        })
+    }
+
+    // Quake qpic has no embedded color table, picture is empty if palette isn't given
+    pub fn parse_with_palette(data: &'a [u8], palette: Option<&'a [u8]>) -> OnlyResult<'a, Self> {
+        let (_, qpic) = Self::parse_indices(data)?;
+        Ok(Self {
+            color_table: palette,
+            ..qpic
+        })
+    }
+
+    fn parse_indices(data: &'a [u8]) -> ParseResult<'a, Self> {
+        let (i, (width, height)) = tuple((le_u32, le_u32))(data)?;
+        let (i, color_indices) = take(width as usize * height as usize)(i)?;
+        Ok((
+            i,
+            Self {
+                width,
+                height,
+                color_indices,
+                color_table: None,
+            },
+        ))
+    }
+
+    pub const fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub const fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.color_table.is_none()
+    }
+
+    pub fn pixels(&self) -> Option<Vec<u8>> {
+        Some(indices_to_rgba(self.color_indices, self.color_table?))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CharInfo {
+    pub offset: u16,
+    pub width: u16,
+}
+
+pub struct Font<'a> {
+    width: u32,
+    height: u32,
+    row_count: u32,
+    row_height: u32,
+    chars: Vec<CharInfo>,
+    color_indices: &'a [u8],
+    color_table: &'a [u8],
+}
+
+impl<'a> Font<'a> {
+    pub fn parse(data: &'a [u8]) -> OnlyResult<'a, Self> {
+        let (i, (width, height, row_count, row_height)) =
+            tuple((le_u32, le_u32, le_u32, le_u32))(data)?;
+        let (i, chars) = count(
+            map(tuple((le_u16, le_u16)), |(offset, width)| CharInfo {
+                offset,
+                width,
+            }),
+            FONT_CHARS_NUM,
+        )(i)?;
+        let (i, color_indices) = take(width as usize * height as usize)(i)?;
+        let (_, color_table) = parse_color_table(i)?;
+        Ok(Self {
+            width,
+            height,
+            row_count,
+            row_height,
+            chars,
+            color_indices,
+            color_table,
+        })
+    }
+
+    pub const fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub const fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub const fn row_count(&self) -> u32 {
+        self.row_count
+    }
+
+    pub const fn row_height(&self) -> u32 {
+        self.row_height
+    }
+
+    pub fn chars(&self) -> &[CharInfo] {
+        &self.chars
+    }
+
+    // Rectangle of the glyph in the image as (x, y, width, height)
+    pub fn glyph_rect(&self, c: u8) -> (u32, u32, u32, u32) {
+        let info = self.chars[c as usize];
+        let offset = u32::from(info.offset);
+        let (x, y) = if self.width == 0 {
+            (0, 0)
+        } else {
+            (offset % self.width, offset / self.width)
+        };
+        (x, y, u32::from(info.width), self.row_height)
+    }
+
+    pub fn pixels(&self) -> Vec<u8> {
+        indices_to_rgba(self.color_indices, self.color_table)
+    }
+}
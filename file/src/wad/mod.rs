@@ -0,0 +1,254 @@
+pub mod lumps;
+pub mod writer;
+
+use crate::{
+    miptex::MipTexture,
+    text::{cstr_bytes, decode},
+};
+use lumps::{Font, Palette, QPic};
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take},
+    combinator::map,
+    multi::count,
+    number::complete::{le_u16, le_u32, le_u8},
+    sequence::tuple,
+};
+use std::{
+    borrow::Cow,
+    collections::{hash_map::Entry as MapEntry, HashMap},
+    iter::Iterator,
+};
+
+const WAD2_MAGIC: &[u8] = b"WAD2";
+const WAD3_MAGIC: &[u8] = b"WAD3";
+const PALETTE_SIZE: usize = 256 * 3;
+const NAME_LEN: usize = 16;
+
+type Input<'a> = &'a [u8];
+type ParseError<'a> = nom::error::VerboseError<Input<'a>>;
+type ParseResult<'a, O> = nom::IResult<Input<'a>, O, ParseError<'a>>;
+type OnlyResult<'a, O> = Result<O, nom::Err<ParseError<'a>>>;
+
+fn take_cstr(i: &[u8], size: usize) -> ParseResult<'_, &[u8]> {
+    map(take(size), cstr_bytes)(i)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Version {
+    Wad2, // Quake
+    #[default]
+    Wad3, // Half-Life
+}
+
+impl Version {
+    const fn magic(self) -> &'static [u8] {
+        match self {
+            Version::Wad2 => WAD2_MAGIC,
+            Version::Wad3 => WAD3_MAGIC,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryType {
+    Palette,
+    QPic,
+    MipTexture,
+    QuakeMipTexture,
+    Font,
+    Unknown(u8),
+}
+
+impl From<u8> for EntryType {
+    fn from(etype: u8) -> Self {
+        match etype {
+            0x40 => Self::Palette,
+            0x42 => Self::QPic,
+            0x43 => Self::MipTexture,
+            0x44 => Self::QuakeMipTexture,
+            0x46 => Self::Font,
+            x => Self::Unknown(x),
+        }
+    }
+}
+
+impl From<EntryType> for u8 {
+    fn from(etype: EntryType) -> Self {
+        match etype {
+            EntryType::Palette => 0x40,
+            EntryType::QPic => 0x42,
+            EntryType::MipTexture => 0x43,
+            EntryType::QuakeMipTexture => 0x44,
+            EntryType::Font => 0x46,
+            EntryType::Unknown(x) => x,
+        }
+    }
+}
+
+pub enum Content<'a> {
+    Palette(Palette<'a>),
+    QPic(QPic<'a>),
+    MipTexture(MipTexture<'a>),
+    Font(Font<'a>),
+    Unknown(&'a [u8]),
+}
+
+pub struct Entry<'a> {
+    raw_name: &'a [u8],
+    etype: EntryType,
+    data: &'a [u8],
+    version: Version,
+    palette: Option<&'a [u8]>,
+}
+
+impl<'a> Entry<'a> {
+    fn parse(
+        i: &'a [u8],
+        file: &'a [u8],
+        version: Version,
+    ) -> ParseResult<'a, (Cow<'a, str>, Self)> {
+        // There's no compression, because I don't find any wad using compression (seems it's LZSS)
+        let (i, (offset, disk_size, _, etype, _, _)) = tuple((
+            map(le_u32, |x| x as usize),
+            map(le_u32, |x| x as usize),
+            le_u32,
+            le_u8,
+            le_u8,
+            le_u16,
+        ))(i)?;
+        let (i, raw_name) = take_cstr(i, NAME_LEN)?;
+
+        let (data_i, _) = take(offset)(file)?;
+        let (_, data) = take(disk_size)(data_i)?;
+
+        let etype = etype.into();
+        Ok((
+            i,
+            (
+                decode(raw_name),
+                Self {
+                    raw_name,
+                    etype,
+                    data,
+                    version,
+                    palette: None,
+                },
+            ),
+        ))
+    }
+
+    // Directory name as stored, so an archive is rebuilt without re-encoding its names
+    pub const fn raw_name(&self) -> &'a [u8] {
+        self.raw_name
+    }
+
+    pub const fn etype(&self) -> EntryType {
+        self.etype
+    }
+
+    pub const fn data(&self) -> &[u8] {
+        self.data
+    }
+
+    pub fn decode(&self) -> OnlyResult<'a, Content<'a>> {
+        Ok(match self.etype {
+            EntryType::Palette => Content::Palette(Palette::parse(self.data)?),
+            EntryType::QPic => Content::QPic(match self.version {
+                Version::Wad2 => QPic::parse_with_palette(self.data, self.palette)?,
+                Version::Wad3 => QPic::parse(self.data)?,
+            }),
+            EntryType::MipTexture => Content::MipTexture(MipTexture::parse(self.data)?),
+            EntryType::QuakeMipTexture => {
+                Content::MipTexture(MipTexture::parse_with_palette(self.data, self.palette)?)
+            }
+            EntryType::Font => Content::Font(Font::parse(self.data)?),
+            EntryType::Unknown(_) => Content::Unknown(self.data),
+        })
+    }
+}
+
+pub struct Archive<'a> {
+    version: Version,
+    entries: Vec<(Cow<'a, str>, Entry<'a>)>, // in order of the directory
+    index: HashMap<String, usize>,           // lowercase
+    duplicates: Vec<Cow<'a, str>>,
+}
+
+impl<'a> Archive<'a> {
+    // WAD2 miptex and qpic use palette of the archive's `palette` entry if there's any
+    pub fn parse(file: &'a [u8]) -> OnlyResult<'a, Self> {
+        Self::parse_impl(file, None)
+    }
+
+    // Palette (256 RGB triples, e.g. `gfx/palette.lmp`) is used for WAD2 entries without own colors
+    pub fn parse_with_palette(file: &'a [u8], palette: &'a [u8]) -> OnlyResult<'a, Self> {
+        Self::parse_impl(file, Some(palette))
+    }
+
+    fn parse_impl(file: &'a [u8], palette: Option<&'a [u8]>) -> OnlyResult<'a, Self> {
+        let (_, (version, dir_num, dir_offset)) = tuple((
+            alt((
+                map(tag(WAD2_MAGIC), |_| Version::Wad2),
+                map(tag(WAD3_MAGIC), |_| Version::Wad3),
+            )),
+            map(le_u32, |x| x as usize),
+            map(le_u32, |x| x as usize),
+        ))(file)?;
+
+        let (dir_i, _) = take(dir_offset)(file)?;
+        let (_, mut entries) = count(|i| Entry::parse(i, file, version), dir_num)(dir_i)?;
+
+        let palette = palette.or_else(|| {
+            entries
+                .iter()
+                .find(|(_, e)| e.etype == EntryType::Palette && e.data.len() >= PALETTE_SIZE)
+                .map(|(_, e)| e.data)
+        });
+        entries.iter_mut().for_each(|(_, e)| e.palette = palette);
+
+        // The engine looks entries up ignoring case and takes the first one
+        let mut index = HashMap::with_capacity(entries.len());
+        let mut duplicates = Vec::new();
+        for (i, (name, _)) in entries.iter().enumerate() {
+            match index.entry(normalize(name)) {
+                MapEntry::Occupied(_) => duplicates.push(name.clone()),
+                MapEntry::Vacant(v) => {
+                    v.insert(i);
+                }
+            }
+        }
+
+        Ok(Self {
+            version,
+            entries,
+            index,
+            duplicates,
+        })
+    }
+
+    pub const fn version(&self) -> Version {
+        self.version
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &Entry<'_>)> {
+        self.entries
+            .iter()
+            .map(|(name, entry)| (name.as_ref(), entry))
+    }
+
+    pub fn get_by_name<S: AsRef<str>>(&self, name: S) -> Option<&Entry<'_>> {
+        self.index
+            .get(&normalize(name.as_ref()))
+            .map(|&i| &self.entries[i].1)
+    }
+
+    // Names of entries shadowed by an earlier entry with the same name
+    pub fn duplicates(&self) -> &[Cow<'a, str>] {
+        &self.duplicates
+    }
+}
+
+fn normalize(name: &str) -> String {
+    name.to_ascii_lowercase()
+}
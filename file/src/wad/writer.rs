@@ -0,0 +1,174 @@
+use super::{Archive, EntryType, Version};
+use crate::text::decode;
+use std::{
+    borrow::Cow,
+    fs::{read, write},
+    io::{Error as IOError, ErrorKind, Result as IOResult, Write},
+    path::Path,
+};
+
+const HEADER_SIZE: usize = 12;
+const DIR_ENTRY_SIZE: usize = 32;
+const MAX_NAME_LEN: usize = 15; // and the null terminator
+const ALIGNMENT: usize = 4;
+
+struct OwnedEntry {
+    name: Vec<u8>,
+    etype: EntryType,
+    data: Vec<u8>,
+}
+
+#[derive(Default)]
+pub struct Builder {
+    version: Version,
+    entries: Vec<OwnedEntry>,
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_version(version: Version) -> Self {
+        Self {
+            version,
+            ..Self::default()
+        }
+    }
+
+    pub fn from_archive(archive: &Archive) -> Self {
+        let mut builder = Self::with_version(archive.version());
+        // Names are copied as they're stored to round-trip high-bit characters
+        for (_, entry) in archive.entries() {
+            if builder.position(entry.raw_name()).is_none() {
+                builder.entries.push(OwnedEntry {
+                    name: entry.raw_name().to_vec(),
+                    etype: entry.etype(),
+                    data: entry.data().to_vec(),
+                });
+            }
+        }
+        builder
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> IOResult<Self> {
+        let file = read(path)?;
+        let archive = Archive::parse(&file)
+            .map_err(|_| IOError::new(ErrorKind::InvalidData, "Invalid wad archive"))?;
+        Ok(Self::from_archive(&archive))
+    }
+
+    fn position<S: AsRef<[u8]>>(&self, name: S) -> Option<usize> {
+        self.entries
+            .iter()
+            .position(|e| e.name.eq_ignore_ascii_case(name.as_ref()))
+    }
+
+    // Entry with the same name (ignoring case like the engine does) is replaced
+    pub fn insert<S: Into<String>>(
+        &mut self,
+        name: S,
+        etype: EntryType,
+        data: Vec<u8>,
+    ) -> IOResult<()> {
+        let name = name.into();
+        if name.is_empty() || name.len() > MAX_NAME_LEN || !name.is_ascii() {
+            return Err(IOError::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid entry name `{}`", name),
+            ));
+        }
+
+        let entry = OwnedEntry {
+            name: name.into_bytes(),
+            etype,
+            data,
+        };
+        match self.position(&entry.name) {
+            Some(i) => self.entries[i] = entry,
+            None => self.entries.push(entry),
+        }
+        Ok(())
+    }
+
+    pub fn insert_miptex<S: Into<String>>(&mut self, name: S, data: Vec<u8>) -> IOResult<()> {
+        self.insert(name, EntryType::MipTexture, data)
+    }
+
+    pub fn insert_qpic<S: Into<String>>(&mut self, name: S, data: Vec<u8>) -> IOResult<()> {
+        self.insert(name, EntryType::QPic, data)
+    }
+
+    pub fn insert_font<S: Into<String>>(&mut self, name: S, data: Vec<u8>) -> IOResult<()> {
+        self.insert(name, EntryType::Font, data)
+    }
+
+    pub fn insert_palette<S: Into<String>>(&mut self, name: S, data: Vec<u8>) -> IOResult<()> {
+        self.insert(name, EntryType::Palette, data)
+    }
+
+    pub fn remove<S: AsRef<str>>(&mut self, name: S) -> bool {
+        if let Some(i) = self.position(name.as_ref()) {
+            self.entries.remove(i);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = Cow<'_, str>> {
+        self.entries.iter().map(|e| decode(&e.name))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let data_size: usize = self.entries.iter().map(|e| align(e.data.len())).sum();
+        let dir_offset = HEADER_SIZE + data_size;
+        let mut out = Vec::with_capacity(dir_offset + self.entries.len() * DIR_ENTRY_SIZE);
+
+        out.extend_from_slice(self.version.magic());
+        out.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+        out.extend_from_slice(&(dir_offset as u32).to_le_bytes());
+
+        let mut offsets = Vec::with_capacity(self.entries.len());
+        for entry in &self.entries {
+            offsets.push(out.len());
+            out.extend_from_slice(&entry.data);
+            out.resize(align(out.len()), 0);
+        }
+
+        for (entry, offset) in self.entries.iter().zip(offsets) {
+            let size = entry.data.len() as u32;
+            out.extend_from_slice(&(offset as u32).to_le_bytes());
+            out.extend_from_slice(&size.to_le_bytes()); // disk size
+            out.extend_from_slice(&size.to_le_bytes()); // size, the same as there's no compression
+            out.push(entry.etype.into());
+            out.push(0); // compression
+            out.extend_from_slice(&[0; 2]); // padding
+            let mut name = [0; MAX_NAME_LEN + 1];
+            let len = entry.name.len().min(MAX_NAME_LEN);
+            name[..len].copy_from_slice(&entry.name[..len]);
+            out.extend_from_slice(&name);
+        }
+        out
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> IOResult<()> {
+        writer.write_all(&self.to_bytes())
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> IOResult<()> {
+        write(path, self.to_bytes())
+    }
+}
+
+const fn align(size: usize) -> usize {
+    size.div_ceil(ALIGNMENT) * ALIGNMENT
+}
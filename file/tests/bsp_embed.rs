@@ -0,0 +1,90 @@
+use file::{
+    bsp::{
+        embed::{embed_textures, strip_textures},
+        lumps::{parse_entities_str, parse_textures},
+        writer::Builder as BspBuilder,
+        LumpType, RawMap,
+    },
+    miptex::encoder::encode,
+    wad::{writer::Builder, Archive},
+};
+
+fn external(name: &str) -> Vec<u8> {
+    let mut header = vec![0; 40];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    header[16..20].copy_from_slice(&16u32.to_le_bytes());
+    header[20..24].copy_from_slice(&16u32.to_le_bytes());
+    header
+}
+
+// Bsp with only entities and textures lumps
+fn make_bsp(entities: &str, textures: &[Vec<u8>]) -> Vec<u8> {
+    let mut lump = (textures.len() as u32).to_le_bytes().to_vec();
+    let mut offset = 4 + 4 * textures.len();
+    for texture in textures {
+        lump.extend_from_slice(&(offset as u32).to_le_bytes());
+        offset += texture.len();
+    }
+    textures.iter().for_each(|x| lump.extend_from_slice(x));
+    let entities = format!("{}\0", entities);
+
+    let mut builder = BspBuilder::new();
+    builder.set_lump(LumpType::Entities, entities.into_bytes());
+    builder.set_lump(LumpType::Textures, lump);
+    builder.to_bytes()
+}
+
+#[test]
+fn embed_and_strip() {
+    let miptex = encode("CRATE", 16, 16, &[200; 16 * 16 * 4]).unwrap();
+    let mut builder = Builder::new();
+    builder.insert_miptex("CRATE", miptex).unwrap();
+    let wad_file = builder.to_bytes();
+    let wad = Archive::parse(&wad_file).unwrap();
+
+    let bsp = make_bsp(
+        "{\n\"classname\" \"worldspawn\"\n\"wad\" \"\\hl\\valve\\halflife.wad;\\hl\\mod\\custom.wad\"\n}\n",
+        &[external("crate"), external("sky")],
+    );
+    let (embedded_bsp, embedded) = embed_textures(&bsp, &[("custom.wad", &wad)]).unwrap();
+    assert_eq!(embedded, ["crate"]);
+    let map = RawMap::parse(&embedded_bsp).unwrap();
+    let textures = parse_textures(map.lump_data(LumpType::Textures)).unwrap();
+    assert!(!textures[0].is_empty());
+    assert!(textures[1].is_empty());
+    assert_eq!(
+        parse_entities_str(map.lump_data(LumpType::Entities)).unwrap(),
+        "{\n\"classname\" \"worldspawn\"\n\"wad\" \"\\hl\\valve\\halflife.wad\"\n}\n"
+    );
+
+    let (stripped_bsp, stripped) = strip_textures(&embedded_bsp, "custom.wad", &wad).unwrap();
+    assert_eq!(stripped, ["CRATE"]);
+    let map = RawMap::parse(&stripped_bsp).unwrap();
+    let textures = parse_textures(map.lump_data(LumpType::Textures)).unwrap();
+    assert!(textures.iter().all(|x| x.is_empty()));
+    assert_eq!(
+        parse_entities_str(map.lump_data(LumpType::Entities)).unwrap(),
+        "{\n\"classname\" \"worldspawn\"\n\"wad\" \"\\hl\\valve\\halflife.wad;custom.wad\"\n}\n"
+    );
+}
+
+#[test]
+fn add_missing_wad_key() {
+    let miptex = encode("CRATE", 16, 16, &[200; 16 * 16 * 4]).unwrap();
+    let bsp = make_bsp(
+        "{\"classname\" \"worldspawn\"}",
+        std::slice::from_ref(&miptex),
+    );
+    let mut builder = Builder::new();
+    builder.insert_miptex("CRATE", miptex).unwrap();
+    let wad_file = builder.to_bytes();
+    let wad = Archive::parse(&wad_file).unwrap();
+
+    let (bsp, stripped) = strip_textures(&bsp, "custom.wad", &wad).unwrap();
+    assert_eq!(stripped, ["CRATE"]);
+    let map = RawMap::parse(&bsp).unwrap();
+    assert_eq!(
+        parse_entities_str(map.lump_data(LumpType::Entities)).unwrap(),
+        "{\"classname\" \"worldspawn\"\n\"wad\" \"custom.wad\"}"
+    );
+}
@@ -0,0 +1,139 @@
+use file::bsp::{
+    hull::{Hull, CONTENTS_EMPTY, CONTENTS_SOLID},
+    lumps::{Clipnode, Plane},
+};
+
+// Solid 32 units cube at the origin, nodes go +x, -x, +y, -y, +z, -z
+fn cube() -> (Vec<Plane>, Vec<Clipnode>) {
+    let normals = [
+        (1.0, 0.0, 0.0),
+        (-1.0, 0.0, 0.0),
+        (0.0, 1.0, 0.0),
+        (0.0, -1.0, 0.0),
+        (0.0, 0.0, 1.0),
+        (0.0, 0.0, -1.0),
+    ];
+    let planes = normals
+        .iter()
+        .map(|&normal| Plane { normal, dist: 16.0 })
+        .collect();
+    let clipnodes = (0..6)
+        .map(|i| Clipnode {
+            plane_id: i,
+            children: [
+                CONTENTS_EMPTY,
+                if i == 5 { CONTENTS_SOLID } else { i as i32 + 1 },
+            ],
+        })
+        .collect();
+    (planes, clipnodes)
+}
+
+#[test]
+fn point_contents() {
+    let (planes, clipnodes) = cube();
+    let hull = Hull::new(&planes, &clipnodes, 0);
+    assert_eq!(hull.point_contents((0.0, 0.0, 0.0)), CONTENTS_SOLID);
+    assert_eq!(hull.point_contents((15.0, -15.0, 15.0)), CONTENTS_SOLID);
+    assert_eq!(hull.point_contents((17.0, 0.0, 0.0)), CONTENTS_EMPTY);
+    assert_eq!(hull.point_contents((0.0, 0.0, -17.0)), CONTENTS_EMPTY);
+}
+
+#[test]
+fn cube_polygons() {
+    let (planes, clipnodes) = cube();
+    let hull = Hull::new(&planes, &clipnodes, 0);
+    let polygons = hull.polygons((-16.0, -16.0, -16.0), (16.0, 16.0, 16.0));
+    assert_eq!(polygons.len(), 6);
+    for polygon in &polygons {
+        assert_eq!(polygon.len(), 4);
+        for &(x, y, z) in polygon {
+            for c in [x, y, z] {
+                assert!((c.abs() - 16.0).abs() < 0.01, "{:?}", polygon);
+            }
+        }
+    }
+}
+
+// Solid below the plane z = 0
+fn floor() -> (Vec<Plane>, Vec<Clipnode>) {
+    let planes = vec![Plane {
+        normal: (0.0, 0.0, 1.0),
+        dist: 0.0,
+    }];
+    let clipnodes = vec![Clipnode {
+        plane_id: 0,
+        children: [CONTENTS_EMPTY, CONTENTS_SOLID],
+    }];
+    (planes, clipnodes)
+}
+
+// Solid below the floor is closed by the bounds, which aren't drawn
+#[test]
+fn floor_polygons() {
+    let (planes, clipnodes) = floor();
+    let hull = Hull::new(&planes, &clipnodes, 0);
+    let polygons = hull.polygons((-100.0, -100.0, -100.0), (100.0, 100.0, 100.0));
+    assert_eq!(polygons.len(), 1);
+    assert!(polygons[0].iter().all(|v| v.2.abs() < 0.01));
+    assert!(polygons[0].iter().any(|v| v.0 > 100.0 && v.1 > 100.0));
+}
+
+#[test]
+fn broken_nodes() {
+    let (planes, mut clipnodes) = cube();
+    clipnodes[5].children[1] = 0; // cycle
+    let hull = Hull::new(&planes, &clipnodes, 0);
+    assert_eq!(hull.point_contents((0.0, 0.0, 0.0)), CONTENTS_SOLID);
+    assert!(hull
+        .polygons((-16.0, -16.0, -16.0), (16.0, 16.0, 16.0))
+        .is_empty());
+    assert_eq!(
+        Hull::new(&planes, &clipnodes, 10).point_contents((0.0, 0.0, 0.0)),
+        CONTENTS_SOLID
+    );
+}
+
+#[test]
+fn trace_hit() {
+    let (planes, clipnodes) = floor();
+    let hull = Hull::new(&planes, &clipnodes, 0);
+    let trace = hull.trace((0.0, 0.0, 10.0), (0.0, 0.0, -10.0));
+    assert!(!trace.start_solid && !trace.all_solid);
+    assert!(trace.fraction < 0.5 && trace.fraction > 0.49);
+    assert_eq!(trace.normal, (0.0, 0.0, 1.0));
+    assert!(trace.end.2 > 0.0 && trace.end.2 < 0.1);
+    assert_eq!(hull.point_contents(trace.end), CONTENTS_EMPTY);
+}
+
+#[test]
+fn trace_open() {
+    let (planes, clipnodes) = floor();
+    let hull = Hull::new(&planes, &clipnodes, 0);
+    let trace = hull.trace((0.0, 0.0, 10.0), (20.0, 0.0, 5.0));
+    assert_eq!(trace.fraction, 1.0);
+    assert_eq!(trace.end, (20.0, 0.0, 5.0));
+}
+
+#[test]
+fn trace_solid() {
+    let (planes, clipnodes) = floor();
+    let hull = Hull::new(&planes, &clipnodes, 0);
+    let trace = hull.trace((0.0, 0.0, -10.0), (0.0, 0.0, -20.0));
+    assert!(trace.start_solid && trace.all_solid);
+    assert_eq!(trace.fraction, 0.0);
+
+    // Leaving solid isn't blocked, but it's reported
+    let trace = hull.trace((0.0, 0.0, -10.0), (0.0, 0.0, 10.0));
+    assert!(trace.start_solid && !trace.all_solid);
+    assert_eq!(trace.fraction, 1.0);
+}
+
+#[test]
+fn trace_cube() {
+    let (planes, clipnodes) = cube();
+    let hull = Hull::new(&planes, &clipnodes, 0);
+    let trace = hull.trace((-40.0, 0.0, 0.0), (40.0, 0.0, 0.0));
+    assert_eq!(trace.normal, (-1.0, 0.0, 0.0));
+    assert!((trace.end.0 + 16.0).abs() < 0.1);
+}
@@ -0,0 +1,22 @@
+use file::detail::DetailTexture;
+
+#[test]
+fn parse_detail_file() {
+    let file = "// comment\r\n{grate\tdetail/metal01 8.0 4\r\nCRATE01 detail/wood02 12\r\n\r\nbroken line\n";
+    let details = DetailTexture::parse_file(file);
+    assert_eq!(
+        details,
+        [
+            DetailTexture {
+                texture: "{grate",
+                detail: "detail/metal01",
+                scale: (8.0, 4.0)
+            },
+            DetailTexture {
+                texture: "CRATE01",
+                detail: "detail/wood02",
+                scale: (12.0, 12.0)
+            },
+        ]
+    );
+}
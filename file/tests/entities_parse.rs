@@ -0,0 +1,44 @@
+use file::map::Entities;
+
+#[test]
+fn keeps_order_and_duplicates() {
+    let lump = "{\r\n\"classname\" \"worldspawn\"\r\n// written by a tool\r\n\"wad\" \"a.wad\"\r\n\"wad\" \"b.wad\"\r\n}\r\n{ \"target\" \"t1\" \"target\" \"t2\" }\n";
+    let entities = Entities::parse(lump).unwrap();
+    let entities = entities.entities();
+    assert_eq!(entities.len(), 2);
+    assert_eq!(
+        entities[0].properties(),
+        [
+            ("classname", "worldspawn"),
+            ("wad", "a.wad"),
+            ("wad", "b.wad")
+        ]
+    );
+    assert_eq!(entities[0].get("wad"), Some("b.wad"));
+    assert_eq!(
+        entities[1].get_all("target").collect::<Vec<_>>(),
+        ["t1", "t2"]
+    );
+    assert_eq!(entities[1].get("classname"), None);
+}
+
+#[test]
+fn reports_position() {
+    let error = |lump| Entities::parse(lump).unwrap_err();
+
+    let err = error("{\n\"classname\" \"worldspawn\"\n}\n{\n  \"origin\" \"0 0 0\n}");
+    assert_eq!((err.line(), err.column()), (5, 12));
+    assert_eq!(err.message(), "unterminated string");
+
+    let err = error("{\n\"classname\" \"info_null\"\n");
+    assert_eq!((err.line(), err.column()), (1, 1));
+    assert_eq!(err.message(), "unterminated entity");
+
+    let err = error("{ \"classname\" }");
+    assert_eq!((err.line(), err.column()), (1, 15));
+    assert_eq!(err.message(), "expected value");
+
+    let err = error("{ }\r\n}");
+    assert_eq!((err.line(), err.column()), (2, 1));
+    assert_eq!(err.to_string(), "expected entity at line 2, column 1");
+}
@@ -0,0 +1,50 @@
+use file::{
+    bsp::{
+        ent::{export_entities, import_entities},
+        writer::Builder,
+        LumpType, RawMap,
+    },
+    map::Entities,
+};
+
+// Bsp with entities and planes lumps
+fn make_bsp(entities: &[u8], planes: &[u8]) -> Vec<u8> {
+    let mut builder = Builder::new();
+    builder.set_lump(LumpType::Entities, entities.to_vec());
+    builder.set_lump(LumpType::Planes, planes.to_vec());
+    builder.to_bytes()
+}
+
+#[test]
+fn serialize_canonical() {
+    let source = "// edited\r\n{ \"classname\" \"worldspawn\" \"wad\" \"a.wad\" }\r\n{\r\n\"classname\" \"light\"\r\n\"_light\" \"255 255 255 200\"\r\n}";
+    let entities = Entities::parse(source).unwrap();
+    let text = entities.to_string();
+    assert_eq!(
+        text,
+        "{\n\"classname\" \"worldspawn\"\n\"wad\" \"a.wad\"\n}\n{\n\"classname\" \"light\"\n\"_light\" \"255 255 255 200\"\n}\n"
+    );
+    assert_eq!(Entities::parse(&text).unwrap().to_string(), text);
+}
+
+#[test]
+fn export_and_import() {
+    let planes = [7; 20];
+    let bsp = make_bsp(b"{\n\"classname\" \"worldspawn\"\n}\n\0", &planes);
+    assert_eq!(
+        export_entities(&bsp).unwrap(),
+        b"{\n\"classname\" \"worldspawn\"\n}\n"
+    );
+
+    let ent = b"{\r\n\"classname\" \"worldspawn\"\r\n\"message\" \"Caf\xe9\"\r\n}\r\n";
+    let imported = import_entities(&bsp, ent).unwrap();
+    let map = RawMap::parse(&imported).unwrap();
+    assert_eq!(
+        map.lump_data(LumpType::Entities),
+        b"{\n\"classname\" \"worldspawn\"\n\"message\" \"Caf\xe9\"\n}\n\0"
+    );
+    assert_eq!(map.lump_data(LumpType::Planes), planes);
+
+    let err = import_entities(&bsp, b"{\n\"classname\" \"worldspawn\n}").unwrap_err();
+    assert_eq!(err.to_string(), "unterminated string at line 2, column 13");
+}
@@ -0,0 +1,70 @@
+use file::{
+    graph::{EdgeKind, Graph},
+    map::Entities,
+};
+
+const ENTITIES: &str = r#"
+{ "classname" "worldspawn" }
+{ "classname" "func_button" "target" "mm" "targetname" "button" }
+{ "classname" "multi_manager" "targetname" "mm" "door" "0" "door#1" "2.5" "ghost" "1" }
+{ "classname" "func_door" "targetname" "door" }
+{ "classname" "trigger_relay" "targetname" "relay" "killtarget" "train" "delay" "3" }
+{ "classname" "func_train" "targetname" "train" "target" "p1" }
+{ "classname" "path_corner" "targetname" "p1" "target" "p2" "message" "relay" }
+{ "classname" "path_corner" "targetname" "p2" "target" "p1" }
+"#;
+
+#[test]
+fn build_graph() {
+    let entities = Entities::parse(ENTITIES).unwrap();
+    let graph = Graph::new(&entities);
+
+    assert_eq!(graph.nodes().len(), 7);
+    let edges: Vec<_> = graph
+        .edges()
+        .iter()
+        .map(|e| (e.from, e.target.as_str(), e.kind, e.delay))
+        .collect();
+    assert_eq!(
+        edges,
+        [
+            (1, "mm", EdgeKind::Target, None),
+            (2, "door", EdgeKind::MultiManager, Some(0.0)),
+            (2, "door", EdgeKind::MultiManager, Some(2.5)),
+            (2, "ghost", EdgeKind::MultiManager, Some(1.0)),
+            (4, "train", EdgeKind::KillTarget, Some(3.0)),
+            (5, "p1", EdgeKind::Target, None),
+            (6, "p2", EdgeKind::PathCorner, None),
+            (6, "relay", EdgeKind::PathCorner, None),
+            (7, "p1", EdgeKind::PathCorner, None),
+        ]
+    );
+    assert_eq!(graph.targets("door"), [3]);
+
+    let dangling: Vec<_> = graph.dangling().map(|e| e.target.as_str()).collect();
+    assert_eq!(dangling, ["ghost"]);
+    let untriggered: Vec<_> = graph.untriggered().map(|n| n.entity).collect();
+    assert_eq!(untriggered, [1]);
+}
+
+#[test]
+fn export() {
+    let entities = Entities::parse(ENTITIES).unwrap();
+    let graph = Graph::new(&entities);
+
+    let dot = graph.to_dot();
+    assert!(dot.starts_with("digraph entities {\n"));
+    assert!(dot.contains("    e1 [label=\"func_button\\nbutton\", color=orange];\n"));
+    assert!(dot.contains("    e2 -> e3 [label=\"multi_manager 2.5s\"];\n"));
+    assert!(dot.contains("    m0 [label=\"ghost\", color=red, style=dashed];\n"));
+    assert!(dot.contains("    e2 -> m0 [label=\"multi_manager 1s\"];\n"));
+
+    let json = graph.to_json();
+    assert!(json.contains(
+        "{\"entity\":1,\"classname\":\"func_button\",\"targetname\":\"button\",\"triggered\":false}"
+    ));
+    assert!(json.contains(
+        "{\"from\":4,\"target\":\"train\",\"to\":[5],\"kind\":\"killtarget\",\"delay\":3}"
+    ));
+    assert!(json.ends_with("\"dangling\":[\"ghost\"],\"untriggered\":[1]}\n"));
+}
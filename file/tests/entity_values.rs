@@ -0,0 +1,97 @@
+use file::map::{wad_name, Entities, Light, ModelRef, RenderMode};
+
+#[test]
+fn typed_values() {
+    let lump = r#"
+{
+"classname" "func_door"
+"targetname" "door1"
+"target" "light1"
+"model" "*12"
+"angle" "-1"
+"rendermode" "5"
+"renderamt" "200"
+"rendercolor" "255 128 0"
+"spawnflags" "257"
+}
+{
+"classname" "light"
+"origin" "16 -32.5 64"
+"angles" "0 90 0"
+"angle" "180"
+"_light" "255 200 100 300"
+"model" "models/barney.mdl"
+}
+{
+"classname" "info_null"
+"origin" "0 0"
+"rendermode" "9"
+"light" "200"
+"angle" "-2"
+}
+"#;
+    let entities = Entities::parse(lump).unwrap();
+    let [door, light, broken] = entities.entities() else {
+        panic!("three entities expected");
+    };
+
+    assert_eq!(door.classname(), Some("func_door"));
+    assert_eq!(door.targetname(), Some("door1"));
+    assert_eq!(door.target(), Some("light1"));
+    assert_eq!(door.model(), Ok(Some(ModelRef::Brush(12))));
+    assert_eq!(door.angles(), Ok(Some((-90.0, 0.0, 0.0))));
+    assert_eq!(door.rendermode(), Ok(Some(RenderMode::Additive)));
+    assert_eq!(door.renderamt(), Ok(Some(200)));
+    assert_eq!(door.rendercolor(), Ok(Some((255, 128, 0))));
+    assert_eq!(door.spawnflags(), Ok(Some(257)));
+    assert_eq!(door.origin(), Ok(None));
+    assert_eq!(door.light(), Ok(None));
+
+    assert_eq!(light.origin(), Ok(Some((16.0, -32.5, 64.0))));
+    // `angles` goes first
+    assert_eq!(light.angles(), Ok(Some((0.0, 90.0, 0.0))));
+    assert_eq!(
+        light.light(),
+        Ok(Some(Light {
+            color: (255.0, 200.0, 100.0),
+            intensity: 300.0
+        }))
+    );
+    assert_eq!(light.model(), Ok(Some(ModelRef::File("models/barney.mdl"))));
+
+    let err = broken.origin().unwrap_err();
+    assert_eq!((err.key(), err.value()), ("origin", "0 0"));
+    assert!(broken.rendermode().is_err());
+    assert_eq!(broken.angles(), Ok(Some((90.0, 0.0, 0.0))));
+    assert_eq!(
+        broken.light(),
+        Ok(Some(Light {
+            color: (200.0, 200.0, 200.0),
+            intensity: 255.0
+        }))
+    );
+}
+
+#[test]
+fn worldspawn_wads() {
+    let lump = r#"
+{
+"classname" "info_null"
+"wad" "other.wad"
+}
+{
+"classname" "worldspawn"
+"wad" "\half-life\valve\halflife.wad;;c:/mod/Custom.wad ; "
+}
+"#;
+    let entities = Entities::parse(lump).unwrap();
+    let worldspawn = entities.worldspawn().unwrap();
+    assert_eq!(
+        worldspawn.wads().collect::<Vec<_>>(),
+        ["\\half-life\\valve\\halflife.wad", "c:/mod/Custom.wad "]
+    );
+    assert_eq!(
+        worldspawn.wads().map(wad_name).collect::<Vec<_>>(),
+        ["halflife.wad", "Custom.wad"]
+    );
+}
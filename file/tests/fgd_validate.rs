@@ -0,0 +1,162 @@
+use file::{
+    fgd::{ClassType, Fgd, KeyType, Problem},
+    map::Entities,
+};
+
+const FGD: &str = r#"
+// Half-Life game definition
+@mapsize(-4096, 4096)
+
+@BaseClass = Targetname [ targetname(target_source) : "Name" ]
+@BaseClass = Target [ target(target_destination) : "Target" ]
+@BaseClass size(-16 -16 -16, 16 16 16) color(255 0 255) = Origin []
+@BaseClass = RenderFields
+[
+    rendermode(choices) : "Render Mode" : 0 =
+    [
+        0: "Normal"
+        5: "Additive"
+    ]
+    renderamt(integer) : "FX Amount (1 - 255)"
+    rendercolor(color255) : "FX Color (R G B)" : "0 0 0"
+]
+
+@SolidClass = worldspawn : "World entity"
+[
+    message(string) : "Map Description / Title"
+    skyname(string) : "environment map (cl_skyname)"
+]
+
+@PointClass base(Origin, Targetname) halfgridsnap color(255 255 0) iconsprite("sprites/lightbulb.spr") = light : "Invisible light source" +
+    " (point entity)"
+[
+    _light(color255) : "Brightness" : "255 255 128 200"
+    style(choices) : "Appearance" : "" =
+    [
+        "" : "Normal"
+        10: "Fluorescent flicker"
+    ]
+    spawnflags(flags) =
+    [
+        1 : "Initially dark" : 0
+        2 : "Unused" : 1
+    ]
+]
+
+@SolidClass base(Targetname, Target, RenderFields) halfgridsnap = func_door : "Basic door"
+[
+    speed(integer) readonly : "Speed" : 100
+    wait(float) : "delay before close, -1 stay open" : 4 : "Seconds"
+]
+
+@MaterialExclusion
+[
+    "dev"
+]
+"#;
+
+#[test]
+fn parse_definitions() {
+    let fgd = Fgd::parse(FGD).unwrap();
+    assert_eq!(fgd.classes().len(), 7);
+
+    let light = fgd.class("LIGHT").unwrap();
+    assert_eq!(light.ctype, ClassType::Point);
+    assert_eq!(
+        light.description.as_deref(),
+        Some("Invisible light source (point entity)")
+    );
+    assert_eq!(light.bases, ["Origin", "Targetname"]);
+    assert_eq!(fgd.color(light), Some((255, 255, 0)));
+    assert_eq!(
+        fgd.size(light),
+        Some(((-16.0, -16.0, -16.0), (16.0, 16.0, 16.0)))
+    );
+    let flags = &light.keys[2].flags;
+    assert_eq!(flags.len(), 2);
+    assert_eq!((flags[1].bit, flags[1].default), (2, true));
+
+    let door = fgd.class("func_door").unwrap();
+    let keys = fgd.keys(door);
+    let names: Vec<_> = keys.iter().map(|k| k.name.as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            "targetname",
+            "target",
+            "rendermode",
+            "renderamt",
+            "rendercolor",
+            "speed",
+            "wait"
+        ]
+    );
+    assert_eq!(keys[2].choices.len(), 2);
+    assert_eq!(keys[5].default.as_deref(), Some("100"));
+    assert_eq!(keys[6].ktype, KeyType::Float);
+    assert_eq!(keys[6].description.as_deref(), Some("Seconds"));
+}
+
+#[test]
+fn validate_entities() {
+    let fgd = Fgd::parse(FGD).unwrap();
+    let entities = Entities::parse(
+        r#"
+{ "classname" "worldspawn" "wad" "halflife.wad" "message" "Test" "_generator" "J.A.C.K." }
+{ "classname" "light" "origin" "0 0 0" "_light" "255 255 300" "style" "10" "spawnflags" "1" }
+{ "classname" "func_door" "model" "*1" "rendermode" "5" "renderamt" "loud" "speed" "100" "wait" "-1" }
+{ "classname" "func_door" "rendermode" "3.0" "colour" "red" }
+{ "classname" "monster_alien" }
+{ "origin" "0 0 0" }
+{ "classname" "Targetname" }
+"#,
+    )
+    .unwrap();
+
+    let issues = fgd.validate(&entities);
+    let problems: Vec<_> = issues.iter().map(|x| (x.entity, &x.problem)).collect();
+    assert_eq!(
+        problems,
+        [
+            (
+                1,
+                &Problem::InvalidValue {
+                    key: "_light".to_string(),
+                    value: "255 255 300".to_string(),
+                    expected: KeyType::Color255
+                }
+            ),
+            (
+                2,
+                &Problem::InvalidValue {
+                    key: "renderamt".to_string(),
+                    value: "loud".to_string(),
+                    expected: KeyType::Integer
+                }
+            ),
+            (
+                3,
+                &Problem::InvalidChoice {
+                    key: "rendermode".to_string(),
+                    value: "3.0".to_string()
+                }
+            ),
+            (3, &Problem::UnknownKey("colour".to_string())),
+            (4, &Problem::UnknownClass),
+            (5, &Problem::MissingClassname),
+            (6, &Problem::UnknownClass),
+        ]
+    );
+    assert_eq!(
+        issues[0].to_string(),
+        "entity 1 (light): value `255 255 300` of key `_light` is not color255"
+    );
+}
+
+#[test]
+fn syntax_error() {
+    let err =
+        Fgd::parse("@PointClass = info_null : \"Empty\"\n[\n  key(string) : \"Key\n]").unwrap_err();
+    assert_eq!((err.line(), err.column()), (3, 17));
+    assert_eq!(err.message(), "unterminated string");
+}
@@ -0,0 +1,41 @@
+use file::miptex::{encoder::encode, MipTexture};
+
+#[test]
+fn encode_and_decode() {
+    let (width, height) = (32, 16);
+    let rgba: Vec<u8> = (0..width * height)
+        .flat_map(|i| {
+            let (x, y) = (i % width, i / width);
+            let a = if x < 16 { 255 } else { 0 };
+            vec![(x * 8) as u8, (y % 8 * 32) as u8, 100, a]
+        })
+        .collect();
+
+    let opaque = encode("GRADIENT", width, height, &rgba).unwrap();
+    let miptex = MipTexture::parse(&opaque).unwrap();
+    assert_eq!(miptex.name(), "GRADIENT");
+    assert_eq!((miptex.main_width(), miptex.main_height()), (32, 16));
+    let pixels = miptex.pixels(0).unwrap();
+    // There're 256 colors, so they are kept exactly
+    assert!(pixels
+        .chunks(4)
+        .zip(rgba.chunks(4))
+        .all(|(a, b)| a[..3] == b[..3] && a[3] == 255));
+    assert_eq!(miptex.pixels(3).unwrap().len(), 4 * 2 * 4);
+
+    let masked = encode("{GRADIENT", width, height, &rgba).unwrap();
+    let miptex = MipTexture::parse(&masked).unwrap();
+    let alpha = miptex
+        .pixels(0)
+        .unwrap()
+        .chunks(4)
+        .map(|x| x[3])
+        .collect::<Vec<_>>();
+    assert!(alpha
+        .iter()
+        .enumerate()
+        .all(|(i, &a)| (a == 255) == (i as u32 % width < 16)));
+
+    assert!(encode("ODD", 30, 16, &rgba).is_err());
+    assert!(encode("TOO_LONG_TEXTURE_NAME", width, height, &rgba).is_err());
+}
@@ -0,0 +1,44 @@
+use file::{
+    bsp::lumps::parse_entities_str,
+    map::Entities,
+    text::{decode, encode_windows_1252},
+    wad::{writer::Builder, Archive},
+};
+
+#[test]
+fn windows_1252_round_trip() {
+    let bytes: Vec<u8> = (1..=255).collect();
+    let text = decode(&bytes);
+    assert_eq!(text.chars().count(), 255);
+    assert_eq!(encode_windows_1252(&text), bytes);
+    assert_eq!(decode(b"caf\xe9 \x80"), "café €");
+    assert_eq!(decode("café".as_bytes()), "café");
+    assert_eq!(encode_windows_1252("日本"), b"??");
+}
+
+#[test]
+fn high_bit_entities() {
+    let lump = b"{\n\"classname\" \"worldspawn\"\n\"message\" \"Caf\xe9 \x93map\x94\"\n}\n\0";
+    let s = parse_entities_str(lump).unwrap();
+    let entities = Entities::parse(&s).unwrap();
+    let message = entities.entities()[0].get("message").unwrap();
+    assert_eq!(message, "Café “map”");
+    assert_eq!(encode_windows_1252(&s), &lump[..lump.len() - 1]);
+}
+
+#[test]
+fn high_bit_wad_names() {
+    let mut builder = Builder::new();
+    builder.insert_miptex("CRATE", vec![1, 2, 3]).unwrap();
+    let mut file = builder.to_bytes();
+    // Patch the name in the directory as Builder accepts ASCII only
+    let name = file.len() - 16;
+    file[name..name + 6].copy_from_slice(b"CR\xc4TE\0");
+
+    let wad = Archive::parse(&file).unwrap();
+    let (name, entry) = wad.entries().next().unwrap();
+    assert_eq!(name, "CRÄTE");
+    assert_eq!(entry.raw_name(), b"CR\xc4TE");
+    assert!(wad.get_by_name("crÄte").is_some());
+    assert_eq!(Builder::from_archive(&wad).to_bytes(), file);
+}
@@ -0,0 +1,24 @@
+use file::vfs::FileSystem;
+use std::fs::{create_dir_all, write};
+
+#[test]
+fn resolve_through_game_dirs() {
+    let base_dir = std::env::temp_dir().join(format!("hlbsp_vfs_test_{}", std::process::id()));
+    create_dir_all(base_dir.join("valve/maps")).unwrap();
+    create_dir_all(base_dir.join("cstrike_addon/MAPS")).unwrap();
+    create_dir_all(base_dir.join("cstrike")).unwrap();
+    write(base_dir.join("valve/maps/crossfire.bsp"), b"valve").unwrap();
+    write(base_dir.join("valve/maps/de_dust.bsp"), b"valve").unwrap();
+    write(base_dir.join("cstrike_addon/MAPS/De_Dust.bsp"), b"addon").unwrap();
+    write(base_dir.join("cstrike/cstrike.wad"), b"wad").unwrap();
+
+    let fs = FileSystem::with_game(&base_dir, "cstrike");
+    assert_eq!(fs.search_paths().len(), 3);
+    assert_eq!(fs.read("maps/de_dust.bsp").unwrap(), b"addon");
+    assert_eq!(fs.read("maps/crossfire.bsp").unwrap(), b"valve");
+    assert!(fs.find_map("de_dust").is_some());
+    assert!(fs.find_wad("CSTRIKE").is_some());
+    assert!(fs.find_model("player.mdl").is_none());
+
+    std::fs::remove_dir_all(&base_dir).unwrap();
+}
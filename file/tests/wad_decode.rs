@@ -0,0 +1,135 @@
+use file::wad::{writer::Builder, Archive, Content, EntryType, Version};
+
+fn color_table() -> Vec<u8> {
+    let mut table = 256u16.to_le_bytes().to_vec();
+    table.extend((0..=255u8).flat_map(|i| vec![i, i, i]));
+    table
+}
+
+#[test]
+fn decode_qpic_and_font() {
+    let mut qpic = [2u32.to_le_bytes(), 1u32.to_le_bytes()].concat();
+    qpic.extend_from_slice(&[10, 200]);
+    qpic.extend(color_table());
+
+    let mut font = [256u32, 2, 1, 2]
+        .iter()
+        .flat_map(|x| x.to_le_bytes())
+        .collect::<Vec<_>>();
+    font.extend(
+        (0..256u16).flat_map(|c| [(c % 32 * 8).to_le_bytes(), 8u16.to_le_bytes()].concat()),
+    );
+    font.extend(vec![0; 256 * 2]);
+    font.extend(color_table());
+
+    let mut builder = Builder::new();
+    builder.insert_qpic("QPIC", qpic).unwrap();
+    builder.insert_font("FONT", font).unwrap();
+    let file = builder.to_bytes();
+    let wad = Archive::parse(&file).unwrap();
+
+    let entry = wad.get_by_name("QPIC").unwrap();
+    assert_eq!(entry.etype(), EntryType::QPic);
+    match entry.decode().unwrap() {
+        Content::QPic(qpic) => {
+            assert_eq!((qpic.width(), qpic.height()), (2, 1));
+            assert_eq!(
+                qpic.pixels().unwrap(),
+                vec![10, 10, 10, 255, 200, 200, 200, 255]
+            );
+        }
+        _ => panic!("Not a qpic"),
+    }
+
+    match wad.get_by_name("FONT").unwrap().decode().unwrap() {
+        Content::Font(font) => {
+            assert_eq!(font.row_height(), 2);
+            assert_eq!(font.glyph_rect(b'!'), (8, 0, 8, 2));
+            assert_eq!(font.pixels().len(), 256 * 2 * 4);
+        }
+        _ => panic!("Not a font"),
+    }
+}
+
+#[test]
+fn decode_wad2_miptex_with_palette() {
+    let mut miptex = b"QUAKE\0\0\0\0\0\0\0\0\0\0\0".to_vec();
+    for x in &[8u32, 8, 40, 104, 120, 124] {
+        miptex.extend_from_slice(&x.to_le_bytes());
+    }
+    miptex.extend(vec![1; 64 + 16 + 4 + 1]);
+    let palette: Vec<u8> = (0..=255u8).flat_map(|i| vec![i, 0, 0]).collect();
+
+    let mut builder = Builder::with_version(Version::Wad2);
+    builder
+        .insert("QUAKE", EntryType::QuakeMipTexture, miptex)
+        .unwrap();
+    let file = builder.to_bytes();
+    assert_eq!(&file[..4], b"WAD2");
+
+    let without_palette = Archive::parse(&file).unwrap();
+    match without_palette
+        .get_by_name("quake")
+        .unwrap()
+        .decode()
+        .unwrap()
+    {
+        Content::MipTexture(miptex) => assert!(miptex.is_empty()),
+        _ => panic!("Not a miptex"),
+    }
+
+    let wad = Archive::parse_with_palette(&file, &palette).unwrap();
+    assert_eq!(wad.version(), Version::Wad2);
+    match wad.get_by_name("quake").unwrap().decode().unwrap() {
+        Content::MipTexture(miptex) => {
+            assert_eq!(miptex.pixels(3).unwrap(), vec![1, 0, 0, 255]);
+        }
+        _ => panic!("Not a miptex"),
+    }
+}
+
+#[test]
+fn decode_wad2_qpic_with_palette() {
+    let mut qpic = [2u32.to_le_bytes(), 1u32.to_le_bytes()].concat();
+    qpic.extend_from_slice(&[10, 200]);
+    let mut palette: Vec<u8> = (0..=255u8).flat_map(|i| vec![0, i, 0]).collect();
+
+    let mut builder = Builder::with_version(Version::Wad2);
+    builder.insert_qpic("CONBACK", qpic.clone()).unwrap();
+    let file = builder.to_bytes();
+    match Archive::parse(&file)
+        .unwrap()
+        .entries()
+        .next()
+        .unwrap()
+        .1
+        .decode()
+        .unwrap()
+    {
+        Content::QPic(qpic) => {
+            assert_eq!((qpic.width(), qpic.height()), (2, 1));
+            assert!(qpic.is_empty() && qpic.pixels().is_none());
+        }
+        _ => panic!("Not a qpic"),
+    }
+
+    let wad = Archive::parse_with_palette(&file, &palette).unwrap();
+    match wad.get_by_name("conback").unwrap().decode().unwrap() {
+        Content::QPic(qpic) => {
+            assert_eq!(qpic.pixels().unwrap(), vec![0, 10, 0, 255, 0, 200, 0, 255]);
+        }
+        _ => panic!("Not a qpic"),
+    }
+
+    // Palette entry of the archive, like `PALETTE` in Quake's gfx.wad
+    palette.iter_mut().for_each(|x| *x /= 2);
+    builder.insert_palette("PALETTE", palette).unwrap();
+    let file = builder.to_bytes();
+    let wad = Archive::parse(&file).unwrap();
+    match wad.get_by_name("conback").unwrap().decode().unwrap() {
+        Content::QPic(qpic) => {
+            assert_eq!(qpic.pixels().unwrap(), vec![0, 5, 0, 255, 0, 100, 0, 255]);
+        }
+        _ => panic!("Not a qpic"),
+    }
+}
@@ -0,0 +1,50 @@
+use file::wad::{writer::Builder, Archive, EntryType};
+
+#[test]
+fn write_and_read_back() {
+    let mut builder = Builder::new();
+    builder.insert_miptex("{GRATE", vec![1, 2, 3]).unwrap();
+    builder.insert_palette("PALETTE", vec![0; 768]).unwrap();
+    builder.insert_miptex("REMOVED", vec![4]).unwrap();
+    builder
+        .insert_miptex("{grate", vec![5, 6, 7, 8, 9])
+        .unwrap();
+    assert!(builder.remove("removed"));
+    assert!(builder
+        .insert_miptex("NAME_LONGER_THAN_15", vec![])
+        .is_err());
+
+    let file = builder.to_bytes();
+    let wad = Archive::parse(&file).unwrap();
+    assert_eq!(wad.entries().count(), 2);
+    let grate = wad.get_by_name("{grate").unwrap();
+    assert_eq!(grate.etype(), EntryType::MipTexture);
+    assert_eq!(grate.data(), &[5, 6, 7, 8, 9]);
+    let palette = wad.get_by_name("PALETTE").unwrap();
+    assert_eq!(palette.etype(), EntryType::Palette);
+    assert_eq!(palette.data().len(), 768);
+
+    let rebuilt = Builder::from_archive(&wad);
+    assert_eq!(rebuilt.to_bytes().len(), file.len());
+}
+
+#[test]
+fn lookup_ignores_case() {
+    let mut builder = Builder::new();
+    builder.insert_miptex("{Grate", vec![1]).unwrap();
+    let mut file = builder.to_bytes();
+    // Patch a second directory entry with the name in other case to get a duplicate
+    let dir = file[file.len() - 32..].to_vec();
+    let mut duplicate = dir.clone();
+    duplicate[16..22].copy_from_slice(b"{GRATE");
+    file.extend(duplicate);
+    file[4..8].copy_from_slice(&2u32.to_le_bytes());
+
+    let wad = Archive::parse(&file).unwrap();
+    assert_eq!(
+        wad.entries().map(|(name, _)| name).collect::<Vec<_>>(),
+        ["{Grate", "{GRATE"]
+    );
+    assert!(wad.get_by_name("{grate").is_some());
+    assert_eq!(wad.duplicates(), ["{GRATE"]);
+}
@@ -7,6 +7,9 @@ flat in uint o_lightmap_offset;
 flat in uvec2 o_lightmap_size;
 
 uniform sampler2D colormap;
+uniform vec2 colormap_size;
+uniform sampler2D detailmap;
+uniform vec2 detail_scale;
 uniform samplerBuffer lightmap;
 
 const bool BILINEAR = true;
@@ -32,7 +35,10 @@ vec4 sample_bilinear_lightmap(in vec2 uv) {
 }
 
 void main() {
-    vec4 color = texture(colormap, o_tex_coords / textureSize(colormap, 0));
+    vec2 uv = o_tex_coords / colormap_size;
+    vec4 color = texture(colormap, uv);
+    // Modulate 2x, so mid-grey detail doesn't change the color
+    color.rgb *= texture(detailmap, uv * detail_scale).rgb * 2.0;
     if (BILINEAR) {
         color *= sample_bilinear_lightmap(o_light_tex_coords);
     } else {
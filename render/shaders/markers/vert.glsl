@@ -0,0 +1,20 @@
+#version 140
+
+in vec3 position;
+in vec3 color;
+
+out vec3 o_color;
+
+uniform mat4 mvp;
+
+const mat4 ROTATE_MAT = mat4(
+	0, 0, -1, 0, 
+	-1, 0, 0, 0, 
+	0, 1, 0, 0, 
+	0, 0, 0, 1
+);
+
+void main() {
+    o_color = color;
+    gl_Position = mvp * ROTATE_MAT * vec4(position, 1.0);
+}
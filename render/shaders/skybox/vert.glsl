@@ -5,8 +5,18 @@ in vec3 position;
 out vec3 reflect_dir;
 
 uniform mat4 mvp;
+uniform vec3 origin;
+uniform vec3 eye;
+
+const mat4 ROTATE_MAT = mat4(
+	0, 0, -1, 0, 
+	-1, 0, 0, 0, 
+	0, 1, 0, 0, 
+	0, 0, 0, 1
+);
 
 void main() {
-    reflect_dir = position;
-    gl_Position = (mvp * vec4(position, 1.0)).xyww;
+    vec4 world_position = ROTATE_MAT * vec4(origin + position, 1.0);
+    reflect_dir = world_position.xyz - eye;
+    gl_Position = mvp * world_position;
 }
@@ -0,0 +1,9 @@
+#version 140
+
+in vec3 o_color;
+
+uniform float alpha;
+
+void main() {
+    gl_FragColor = vec4(o_color, alpha);
+}
@@ -0,0 +1,23 @@
+#version 140
+
+in vec3 position;
+in vec3 normal;
+in vec3 color;
+
+out vec3 o_color;
+
+uniform mat4 mvp;
+
+const mat4 ROTATE_MAT = mat4(
+	0, 0, -1, 0, 
+	-1, 0, 0, 0, 
+	0, 1, 0, 0, 
+	0, 0, 0, 1
+);
+
+void main() {
+    // Faces are shaded by their direction, so flat volumes still show their shape
+    float shade = 0.6 + 0.3 * abs(normal.z) + 0.1 * abs(normal.x);
+    o_color = color * shade;
+    gl_Position = mvp * ROTATE_MAT * vec4(position, 1.0);
+}
@@ -0,0 +1,127 @@
+use super::Error;
+use file::{
+    bsp::{
+        hull::{Hull, Trace, HULL_SIZES},
+        lumps::*,
+        LumpType, RawMap,
+    },
+    map::{Entities, ModelRef},
+};
+
+const TRIGGER_CLASSNAME_PREFIX: &str = "trigger_";
+// Brush entities the player walks through, triggers aside
+const NON_SOLID_CLASSNAMES: [&str; 2] = ["func_illusionary", "func_water"];
+
+pub fn is_trigger(classname: &str) -> bool {
+    classname.starts_with(TRIGGER_CLASSNAME_PREFIX)
+}
+
+fn offset(v: Vec3, o: Vec3, sign: f32) -> Vec3 {
+    (v.0 + o.0 * sign, v.1 + o.1 * sign, v.2 + o.2 * sign)
+}
+
+// Clip hulls of the world and solid brush entities as they are placed in the map
+pub struct Collision {
+    planes: Vec<Plane>,
+    clipnodes: Vec<Clipnode>,
+    models: Vec<Model>, // the world first
+}
+
+impl Collision {
+    pub fn new(map: &RawMap, entities: &Entities) -> Result<Self, Error> {
+        let planes = parse_planes(map.lump_data(LumpType::Planes))
+            .map_err(|_| Error::Parse("planes lump"))?;
+        let clipnodes = parse_clipnodes(map.lump_data(LumpType::Clipnodes))
+            .map_err(|_| Error::Parse("clipnodes lump"))?;
+        let mut models = parse_models(map.lump_data(LumpType::Models))
+            .map_err(|_| Error::Parse("models lump"))?
+            .into_iter()
+            .map(Some)
+            .collect::<Vec<_>>();
+
+        for entity in entities.entities() {
+            let classname = entity.classname().unwrap_or_default();
+            if !is_trigger(classname) && !NON_SOLID_CLASSNAMES.contains(&classname) {
+                continue;
+            }
+            if let Ok(Some(ModelRef::Brush(index))) = entity.model() {
+                if let Some(model) = models.get_mut(index).filter(|_| index > 0) {
+                    *model = None;
+                }
+            }
+        }
+        // Models no entity uses are left too, compilers don't write ones without brushes
+        Ok(Self {
+            planes,
+            clipnodes,
+            models: models.into_iter().flatten().collect(),
+        })
+    }
+
+    // Only hulls 1-3 are clipnodes, the headnode of hull 0 is one of the BSP nodes
+    fn hulls(&self, hull: usize) -> Option<impl Iterator<Item = (Hull<'_>, &Model)>> {
+        if !(1..HULL_SIZES.len()).contains(&hull) {
+            return None;
+        }
+        Some(self.models.iter().map(move |model| {
+            (
+                Hull::new(&self.planes, &self.clipnodes, model.headnodes[hull]),
+                model,
+            )
+        }))
+    }
+
+    // Faces of hull 1-3 for drawing, bounds of models are expanded by the hull size
+    pub fn polygons(&self, hull: usize) -> Option<Vec<Vec<Vec3>>> {
+        let hulls = self.hulls(hull)?;
+        let (size_mins, size_maxs) = HULL_SIZES[hull];
+        let polygons = hulls
+            .flat_map(|(clip, model)| {
+                let mins = offset(model.mins, size_mins, 1.0);
+                let maxs = offset(model.maxs, size_maxs, 1.0);
+                clip.polygons(mins, maxs).into_iter().map(move |polygon| {
+                    polygon
+                        .into_iter()
+                        .map(|v| offset(v, model.origin, 1.0))
+                        .collect()
+                })
+            })
+            .collect();
+        Some(polygons)
+    }
+
+    // The nearest hit of all models, the point is the origin of a box of the hull size
+    pub fn trace(&self, hull: usize, start: Vec3, end: Vec3) -> Option<Trace> {
+        let trace = self
+            .hulls(hull)?
+            .map(|(clip, model)| {
+                let trace = clip.trace(
+                    offset(start, model.origin, -1.0),
+                    offset(end, model.origin, -1.0),
+                );
+                Trace {
+                    end: offset(trace.end, model.origin, 1.0),
+                    ..trace
+                }
+            })
+            .fold(None, |nearest: Option<Trace>, trace| {
+                Some(match nearest {
+                    Some(nearest) => {
+                        let (near, far) = if trace.fraction < nearest.fraction {
+                            (trace, nearest)
+                        } else {
+                            (nearest, trace)
+                        };
+                        Trace {
+                            start_solid: near.start_solid || far.start_solid,
+                            all_solid: near.all_solid || far.all_solid,
+                            ..near
+                        }
+                    }
+                    None => trace,
+                })
+            })
+            .unwrap_or(Trace::clear(end));
+        Some(trace)
+    }
+}
@@ -1,42 +1,61 @@
-use file::{
-    bsp::lumps::parse_entities_str,
-    map::{Entities, Entity},
-};
+pub use file::bsp::lumps::Vec3;
+use file::map::{wad_name, Entities};
+use log::{error, warn};
 
-const INFO_PLAYER_START_CLASSNAME: &str = "info_player_start";
-
-pub type Vec3 = (f32, f32, f32);
-
-pub fn parse_entities(i: &[u8]) -> Option<Entities> {
-    let s = parse_entities_str(i).ok()?; // TODO : do not ok
-    Entities::parse(s).ok() // TODO : same as above
+pub fn parse_entities(s: &str) -> Option<Entities<'_>> {
+    Entities::parse(s)
+        .map_err(|err| error!("Error parsing entities: {}", err))
+        .ok()
 }
 
 pub fn get_skyname(entities: &Entities) -> Option<String> {
     entities
         .entities()
         .iter()
-        .find_map(|e| e.properties().get("skyname"))
+        .find_map(|e| e.get("skyname"))
         .map(|e| e.to_string())
 }
 
-pub fn find_info_player_start<'a>(entities: &'a Entities) -> Option<&'a Entity<'a>> {
-    entities.entities().iter().find(|e| {
-        e.properties()
-            .get("classname")
-            .filter(|&classname| classname == &INFO_PLAYER_START_CLASSNAME)
-            .is_some()
-    })
+pub fn get_wad_names(entities: &Entities) -> Vec<String> {
+    entities
+        .worldspawn()
+        .map(|worldspawn| {
+            worldspawn
+                .wads()
+                .map(wad_name)
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
 }
 
-fn parse_vector3(s: &str) -> Option<Vec3> {
-    let mut n = s.split(' ').flat_map(|e| e.parse().ok());
-    Some((n.next()?, n.next()?, n.next()?))
+// Place and view of an entity the camera can start at
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Spawn {
+    pub origin: Vec3,
+    pub angles: Vec3, // pitch, yaw and roll
 }
 
-pub fn get_start_point(entity: &Entity) -> Option<Vec3> {
-    entity
-        .properties()
-        .get("origin")
-        .and_then(|o| parse_vector3(o))
+// Any class can be a spawn, e.g. `info_player_deathmatch` or `info_player_coop`
+pub fn get_spawns(entities: &Entities) -> Vec<(String, Spawn)> {
+    entities
+        .entities()
+        .iter()
+        .filter_map(|e| Some((e.classname()?, e)))
+        .filter_map(|(classname, e)| {
+            let origin = e
+                .origin()
+                .map_err(|err| warn!("`{}` has {}", classname, err))
+                .ok()
+                .flatten()?;
+            let angles = e
+                .angles()
+                .map_err(|err| warn!("`{}` has {}", classname, err))
+                .ok()
+                .flatten()
+                .unwrap_or_default();
+            Some((classname.to_string(), Spawn { origin, angles }))
+        })
+        .collect()
 }
@@ -0,0 +1,22 @@
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    io::Error as IOError,
+    path::PathBuf,
+};
+
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, IOError),
+    Parse(&'static str),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Error::Io(path, err) => write!(f, "Error reading {:?}: {}", path, err),
+            Error::Parse(what) => write!(f, "Error parsing {}", what),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
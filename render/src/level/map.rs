@@ -1,10 +1,8 @@
+use super::Error;
+use crate::texture::{TextureData, TextureSource};
 use cgmath::Matrix4;
 use elapsed::measure_time;
-use file::{
-    bsp::{lumps::*, LumpType, RawMap},
-    miptex::MipTexture,
-    wad::Archive,
-};
+use file::bsp::{lumps::*, LumpType, RawMap};
 use glium::{
     backend::Facade,
     implement_vertex,
@@ -15,18 +13,21 @@ use glium::{
         MipmapsOption, RawImage2d, Texture2d,
     },
     uniform,
-    uniforms::MinifySamplerFilter,
+    uniforms::{MinifySamplerFilter, SamplerWrapFunction},
     vertex::{VertexBuffer, VertexBufferAny},
     DrawParameters, Program, Rect, Surface,
 };
 use itertools::Itertools;
-use log::{debug, info};
+use log::{debug, info, warn};
 use std::{
     collections::{HashMap, HashSet},
     iter::Iterator,
 };
 
-const TRANSPARENT_TEXTURES: [&str; 2] = ["sky", "aaatrigger"];
+const TRANSPARENT_TEXTURES: [&str; 1] = ["aaatrigger"];
+const SKY_TEXTURE: &str = "sky";
+const MISSING_CELL_SIZE: u32 = 16;
+const MISSING_COLORS: [[u8; 4]; 2] = [[255, 0, 255, 255], [0, 0, 0, 255]];
 
 #[derive(Copy, Clone)]
 struct Vertex {
@@ -69,28 +70,58 @@ fn triangulate(vertices: Vec<usize>) -> Vec<usize> {
     }
 }
 
+fn checkerboard(width: u32, height: u32) -> Vec<u8> {
+    (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .flat_map(|(x, y)| {
+            let cell = (x / MISSING_CELL_SIZE + y / MISSING_CELL_SIZE) % 2;
+            MISSING_COLORS[cell as usize]
+        })
+        .collect()
+}
+
+struct TextureUsage {
+    width: u32,
+    height: u32,
+    faces: usize,
+}
+
 pub struct Map {
     origin: [f32; 3],
     vbo: VertexBufferAny,
+    sky_ibo: Option<IndexBufferAny>,
     textured_ibos: HashMap<String, IndexBufferAny>, // lowercase
     textures: HashMap<String, Texture2d>,           // lowercase
+    texture_usage: HashMap<String, TextureUsage>,
+    placeholders: HashSet<String>,
+    details: HashMap<String, (Texture2d, [f32; 2])>,
+    no_detail: Texture2d,
+    details_enabled: bool,
     lightmap: BufferTexture<[u8; 4]>,
     program: Program,
 }
 
 impl Map {
-    pub fn new<F: ?Sized + Facade>(facade: &F, map: &RawMap) -> Self {
-        let vertices = parse_vertices(map.lump_data(LumpType::Vertices)).unwrap();
-        let edges = parse_edges(map.lump_data(LumpType::Edges)).unwrap();
-        let surfedges = parse_surfedges(map.lump_data(LumpType::Surfegdes)).unwrap();
-        let normals = parse_normals_from_planes(map.lump_data(LumpType::Planes)).unwrap();
-        let faces = parse_faces(map.lump_data(LumpType::Faces)).unwrap();
+    pub fn new<F: ?Sized + Facade>(facade: &F, map: &RawMap) -> Result<Self, Error> {
+        let vertices = parse_vertices(map.lump_data(LumpType::Vertices))
+            .map_err(|_| Error::Parse("vertices lump"))?;
+        let edges =
+            parse_edges(map.lump_data(LumpType::Edges)).map_err(|_| Error::Parse("edges lump"))?;
+        let surfedges = parse_surfedges(map.lump_data(LumpType::Surfegdes))
+            .map_err(|_| Error::Parse("surfedges lump"))?;
+        let normals = parse_normals_from_planes(map.lump_data(LumpType::Planes))
+            .map_err(|_| Error::Parse("planes lump"))?;
+        let faces =
+            parse_faces(map.lump_data(LumpType::Faces)).map_err(|_| Error::Parse("faces lump"))?;
         let lightmap = map.lump_data(LumpType::Lighting);
-        let texinfos = parse_texinfos(map.lump_data(LumpType::TexInfo)).unwrap();
-        let textures = parse_textures(map.lump_data(LumpType::Textures)).unwrap();
-        let models = parse_models(map.lump_data(LumpType::Models)).unwrap();
+        let texinfos = parse_texinfos(map.lump_data(LumpType::TexInfo))
+            .map_err(|_| Error::Parse("texinfos lump"))?;
+        let textures = parse_textures(map.lump_data(LumpType::Textures))
+            .map_err(|_| Error::Parse("textures lump"))?;
+        let models = parse_models(map.lump_data(LumpType::Models))
+            .map_err(|_| Error::Parse("models lump"))?;
 
-        let root_model = &models[0];
+        let root_model = models.first().ok_or(Error::Parse("models lump"))?;
 
         let origin = {
             let o = root_model.origin;
@@ -105,32 +136,56 @@ impl Map {
             .sum();
         let mut vbo_vertices = Vec::with_capacity(vbo_size);
         let mut loaded_textures = HashMap::new();
+        let mut texture_usage = HashMap::new();
 
-        let textured_ibos: HashMap<_, _> = faces
+        let mut textured_ibos: HashMap<_, _> = faces
             .iter()
             .skip(root_model.face_id)
             .take(root_model.face_num)
-            .filter_map(|f| {
-                let texinfo = &texinfos[f.texinfo_id];
-                let texture = &textures[texinfo.texture_id];
+            .filter(|f| f.surfedge_num > 0)
+            .map(|f| {
+                let texinfo = texinfos
+                    .get(f.texinfo_id)
+                    .ok_or(Error::Parse("faces lump"))?;
+                let texture = textures
+                    .get(texinfo.texture_id)
+                    .ok_or(Error::Parse("texinfo lump"))?;
                 let tex_name = texture.name().to_string();
 
                 if TRANSPARENT_TEXTURES
                     .iter()
                     .any(|x| tex_name.eq_ignore_ascii_case(x))
                 {
-                    return None;
+                    return Ok(None);
                 }
 
-                if !texture.is_empty() && !loaded_textures.contains_key(&tex_name) {
+                let is_sky = tex_name.eq_ignore_ascii_case(SKY_TEXTURE);
+                let tex_name = if is_sky {
+                    SKY_TEXTURE.to_string()
+                } else {
+                    tex_name
+                };
+
+                texture_usage
+                    .entry(tex_name.clone())
+                    .or_insert(TextureUsage {
+                        width: texture.main_width(),
+                        height: texture.main_height(),
+                        faces: 0,
+                    })
+                    .faces += 1;
+
+                if !is_sky && !texture.is_empty() && !loaded_textures.contains_key(&tex_name) {
                     let (elapsed, ()) = measure_time(|| {
-                        loaded_textures
-                            .insert(tex_name.clone(), Self::upload_miptex(facade, texture));
+                        if let Some(data) = TextureData::from_miptex(texture) {
+                            loaded_textures
+                                .insert(tex_name.clone(), Self::upload_texture(facade, &data));
+                        }
                     });
                     debug!("Load intern miptex `{}` in {}", &tex_name, elapsed);
                 }
 
-                let n = &normals[f.plane_id];
+                let n = normals.get(f.plane_id).ok_or(Error::Parse("faces lump"))?;
                 let normal = if f.side {
                     [n.0, n.1, n.2]
                 } else {
@@ -144,22 +199,24 @@ impl Map {
                     .skip(f.surfedge_id)
                     .take(f.surfedge_num)
                     .map(|&s| {
-                        let i = if s < 0 {
-                            edges[-s as usize].1
-                        } else {
-                            edges[s as usize].0
-                        } as usize;
-                        &vertices[i]
-                    })
-                    .map(move |v| Vertex {
-                        position: [v.0, v.1, v.2],
-                        tex_coords: calculate_uvs(&v, texinfo),
-                        light_tex_coords: [0.0, 0.0],
-                        lightmap_offset: (lightmap_offset / 3) as u32,
-                        lightmap_size: [0, 0],
-                        normal,
+                        let edge = edges
+                            .get(s.unsigned_abs() as usize)
+                            .ok_or(Error::Parse("surfedges lump"))?;
+                        let i = if s < 0 { edge.1 } else { edge.0 } as usize;
+                        let v = vertices.get(i).ok_or(Error::Parse("edges lump"))?;
+                        Ok(Vertex {
+                            position: [v.0, v.1, v.2],
+                            tex_coords: calculate_uvs(v, texinfo),
+                            light_tex_coords: [0.0, 0.0],
+                            lightmap_offset: (lightmap_offset / 3) as u32,
+                            lightmap_size: [0, 0],
+                            normal,
+                        })
                     })
-                    .collect_vec();
+                    .collect::<Result<Vec<_>, Error>>()?;
+                if verts.is_empty() {
+                    return Ok(None);
+                }
 
                 let ([mut min_u, mut min_v], [mut max_u, mut max_v]) =
                     (verts[0].tex_coords, verts[0].tex_coords);
@@ -192,8 +249,11 @@ impl Map {
                 let end = vbo_vertices.len();
                 let indices = triangulate((begin..end).collect_vec());
 
-                Some((tex_name, indices))
+                Ok(Some((tex_name, indices)))
             })
+            .filter_map(Result::transpose)
+            .collect::<Result<Vec<_>, Error>>()?
+            .into_iter()
             .into_group_map()
             .into_iter()
             .map(|(k, v)| {
@@ -208,6 +268,8 @@ impl Map {
             })
             .collect();
 
+        // Sky faces aren't textured, they are portals to the skybox
+        let sky_ibo = textured_ibos.remove(SKY_TEXTURE);
         let vbo = VertexBuffer::new(facade, &vbo_vertices).unwrap().into();
 
         let (elapsed, program) = measure_time(|| {
@@ -236,6 +298,10 @@ impl Map {
         });
         debug!("Lightmap was loaded in {}", elapsed);
 
+        // Mid-grey is neutral for modulate 2x, so it's used for textures without details
+        let no_detail =
+            Texture2d::new(facade, RawImage2d::from_raw_rgba(vec![128u8; 4], (1, 1))).unwrap();
+
         info!(
             "Map summary: [Vertices={}, Texture groups={}, Lightmap texels={}]",
             vbo_vertices.len(),
@@ -243,27 +309,36 @@ impl Map {
             lightmap.len()
         );
 
-        Self {
+        Ok(Self {
             origin,
             vbo,
+            sky_ibo,
             textured_ibos,
             textures: loaded_textures,
+            texture_usage,
+            placeholders: HashSet::new(),
+            details: HashMap::new(),
+            no_detail,
+            details_enabled: true,
             lightmap,
             program,
-        }
+        })
     }
 
-    fn upload_miptex<F: ?Sized + Facade>(facade: &F, miptex: &MipTexture) -> Texture2d {
+    fn upload_texture<F: ?Sized + Facade>(facade: &F, data: &TextureData) -> Texture2d {
         let texture = Texture2d::empty_with_mipmaps(
             facade,
-            MipmapsOption::EmptyMipmapsMax((MipTexture::layers() - 1) as u32),
-            miptex.main_width(),
-            miptex.main_height(),
+            MipmapsOption::EmptyMipmapsMax(data.mip_levels.len().saturating_sub(1) as u32),
+            data.width,
+            data.height,
         )
         .unwrap();
 
-        for i in 0..texture.get_mipmap_levels() {
-            let miplevel = texture.mipmap(i).unwrap();
+        for (i, pixels) in data.mip_levels.iter().enumerate() {
+            let miplevel = match texture.mipmap(i as u32) {
+                Some(miplevel) => miplevel,
+                None => break,
+            };
             let dims = (miplevel.width(), miplevel.height());
             let rect = Rect {
                 left: 0,
@@ -271,35 +346,146 @@ impl Map {
                 width: dims.0,
                 height: dims.1,
             };
-            let pixels = miptex.pixels(i as usize).unwrap();
-            let image = RawImage2d::from_raw_rgba_reversed(&pixels, dims);
+            let image = RawImage2d::from_raw_rgba_reversed(pixels, dims);
             miplevel.write(rect, image);
         }
         texture
     }
 
     pub fn is_textures_loaded(&self) -> bool {
-        self.textured_ibos.len() == self.textures.len()
+        self.missing_textures().next().is_none()
+    }
+
+    // Textures drawn as placeholders are still reported as missing
+    pub fn missing_textures(&self) -> impl Iterator<Item = &str> {
+        self.textured_ibos
+            .keys()
+            .filter(move |name| {
+                !self.textures.contains_key(*name) || self.placeholders.contains(*name)
+            })
+            .map(String::as_str)
+    }
+
+    // Replaces textures which weren't found with checkerboard of the same size, so UVs still match
+    pub fn fill_missing_textures<F: ?Sized + Facade>(&mut self, facade: &F) {
+        let mut missing = self
+            .missing_textures()
+            .filter(|name| !self.placeholders.contains(*name))
+            .map(|name| (name.to_string(), &self.texture_usage[name]))
+            .collect_vec();
+        if missing.is_empty() {
+            return;
+        }
+        missing
+            .sort_by(|(a, a_usage), (b, b_usage)| b_usage.faces.cmp(&a_usage.faces).then(a.cmp(b)));
+
+        warn!("{} textures not found:", missing.len());
+        let placeholders = missing
+            .into_iter()
+            .filter_map(|(name, usage)| {
+                warn!(
+                    "  `{}` ({}x{}) used by {} faces",
+                    name, usage.width, usage.height, usage.faces
+                );
+                // Size comes from the miptex header, which may be broken
+                let (width, height) = (usage.width.max(1), usage.height.max(1));
+                let image = RawImage2d::from_raw_rgba(checkerboard(width, height), (width, height));
+                match Texture2d::new(facade, image) {
+                    Ok(texture) => Some((name, texture)),
+                    Err(err) => {
+                        warn!("  `{}` placeholder can't be created: {}", name, err);
+                        None
+                    }
+                }
+            })
+            .collect_vec();
+        self.placeholders
+            .extend(placeholders.iter().map(|(name, _)| name.clone()));
+        self.textures.extend(placeholders);
     }
 
-    pub fn load_from_archive<F: ?Sized + Facade>(&mut self, facade: &F, archive: &Archive) {
-        let present: HashSet<_> = self.textures.keys().cloned().collect();
-        let required: HashSet<_> = self.textured_ibos.keys().cloned().collect();
-        let loaded = required.difference(&present).cloned().filter_map(|name| {
+    pub fn load_from_source<F: ?Sized + Facade, T: ?Sized + TextureSource>(
+        &mut self,
+        facade: &F,
+        source: &T,
+    ) {
+        let missing = self.missing_textures().map(str::to_string).collect_vec();
+        for name in missing {
             let (elapsed, tex2d) = measure_time(|| {
-                let entry = archive
-                    .get_by_name(name.to_ascii_uppercase())
-                    .or_else(|| archive.get_by_name(name.to_ascii_lowercase()))?;
-                let miptex = MipTexture::parse(entry.data()).ok()?;
-                Some(Self::upload_miptex(facade, &miptex))
+                source
+                    .find(&name)
+                    .map(|data| Self::upload_texture(facade, &data))
             });
-            if tex2d.is_some() {
-                debug!("Load extern miptex `{}` in {}", &name, elapsed);
+            if let Some(tex2d) = tex2d {
+                debug!("Load extern texture `{}` in {}", &name, elapsed);
+                self.placeholders.remove(&name);
+                self.textures.insert(name, tex2d);
             }
+        }
+    }
 
-            Some((name, tex2d?))
-        });
-        self.textures.extend(loaded);
+    // Unlike `load_from_source`, every texture found in the source is replaced
+    pub fn replace_from_source<F: ?Sized + Facade, T: ?Sized + TextureSource>(
+        &mut self,
+        facade: &F,
+        source: &T,
+    ) {
+        let names = self.textured_ibos.keys().cloned().collect_vec();
+        for name in names {
+            if let Some(data) = source.find(&name) {
+                debug!(
+                    "Replace texture `{}` with {}x{} one",
+                    &name, data.width, data.height
+                );
+                self.placeholders.remove(&name);
+                self.textures
+                    .insert(name, Self::upload_texture(facade, &data));
+            }
+        }
+    }
+
+    pub fn find_texture_name<S: AsRef<str>>(&self, name: S) -> Option<&str> {
+        self.textured_ibos
+            .keys()
+            .find(|x| x.eq_ignore_ascii_case(name.as_ref()))
+            .map(String::as_str)
+    }
+
+    pub fn set_detail<F: ?Sized + Facade>(
+        &mut self,
+        facade: &F,
+        texture_name: &str,
+        data: &TextureData,
+        scale: (f32, f32),
+    ) {
+        if let Some(name) = self.find_texture_name(texture_name).map(str::to_string) {
+            let detail = Self::upload_texture(facade, data);
+            self.details.insert(name, (detail, [scale.0, scale.1]));
+        }
+    }
+
+    pub fn details_num(&self) -> usize {
+        self.details.len()
+    }
+
+    pub fn set_details_enabled(&mut self, enabled: bool) {
+        self.details_enabled = enabled;
+    }
+
+    pub const fn details_enabled(&self) -> bool {
+        self.details_enabled
+    }
+
+    pub const fn origin(&self) -> [f32; 3] {
+        self.origin
+    }
+
+    pub const fn vbo(&self) -> &VertexBufferAny {
+        &self.vbo
+    }
+
+    pub const fn sky_ibo(&self) -> Option<&IndexBufferAny> {
+        self.sky_ibo.as_ref()
     }
 
     pub fn render<S: Surface>(
@@ -314,10 +500,28 @@ impl Map {
         let mvp: [[f32; 4]; 4] = mvp.into();
         self.textured_ibos.iter().for_each(|(tex, ibo)| {
             if let Some(colormap) = self.textures.get(tex) {
+                // UVs are in texels of the original miptex, even if it's replaced with other size
+                let colormap_size = self
+                    .texture_usage
+                    .get(tex)
+                    .map_or([colormap.width() as f32, colormap.height() as f32], |usage| {
+                        [usage.width as f32, usage.height as f32]
+                    });
+                let (detailmap, detail_scale) = self
+                    .details
+                    .get(tex)
+                    .filter(|_| self.details_enabled)
+                    .map_or((&self.no_detail, [1.0, 1.0]), |(detail, scale)| (detail, *scale));
                 let uniforms = uniform! {
                     mvp: mvp,
                     origin: self.origin,
                     colormap: colormap.sampled().minify_filter(MinifySamplerFilter::LinearMipmapNearest),
+                    colormap_size: colormap_size,
+                    detailmap: detailmap
+                        .sampled()
+                        .minify_filter(MinifySamplerFilter::LinearMipmapLinear)
+                        .wrap_function(SamplerWrapFunction::Repeat),
+                    detail_scale: detail_scale,
                     lightmap: lightmap,
                 };
                 surface
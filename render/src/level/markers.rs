@@ -0,0 +1,241 @@
+use super::entities::Vec3;
+use cgmath::{Deg, Matrix3, Vector3};
+use elapsed::measure_time;
+use file::{
+    fgd::{ClassType, Fgd},
+    map::{Entities, ModelRef},
+};
+use glium::{
+    backend::Facade,
+    implement_vertex,
+    index::{IndexBuffer, PrimitiveType},
+    program, uniform,
+    vertex::VertexBuffer,
+    DrawParameters, Program, Surface,
+};
+use log::{debug, info};
+
+// Hammer draws point entities without size as 16 units cube
+const DEFAULT_SIZE: (Vec3, Vec3) = ((-8.0, -8.0, -8.0), (8.0, 8.0, 8.0));
+const BOX_EDGES: [(u32, u32); 12] = [
+    (0, 1),
+    (1, 3),
+    (3, 2),
+    (2, 0),
+    (4, 5),
+    (5, 7),
+    (7, 6),
+    (6, 4),
+    (0, 4),
+    (1, 5),
+    (2, 6),
+    (3, 7),
+];
+const VERTICES_PER_MARKER: u32 = 10; // corners, center and tip of the facing line
+
+#[derive(Copy, Clone)]
+struct Vertex {
+    position: [f32; 3],
+    color: [f32; 3],
+}
+
+implement_vertex!(Vertex, position, color);
+
+struct Marker {
+    classname: String,
+    origin: Vec3,
+    angles: Vec3,
+    size: (Vec3, Vec3),
+    color: (u8, u8, u8),
+    visible: bool,
+}
+
+impl Marker {
+    fn vertices(&self) -> impl Iterator<Item = Vertex> {
+        let rotation = Matrix3::from_angle_z(Deg(self.angles.1))
+            * Matrix3::from_angle_y(Deg(self.angles.0))
+            * Matrix3::from_angle_x(Deg(self.angles.2));
+        let origin = Vector3::new(self.origin.0, self.origin.1, self.origin.2);
+        let (mins, maxs) = self.size;
+        let corners = (0..8).map(move |i| {
+            Vector3::new(
+                if i & 1 == 0 { mins.0 } else { maxs.0 },
+                if i & 2 == 0 { mins.1 } else { maxs.1 },
+                if i & 4 == 0 { mins.2 } else { maxs.2 },
+            )
+        });
+        // The facing line goes from the center through the front side
+        let center = Vector3::new(
+            (mins.0 + maxs.0) / 2.0,
+            (mins.1 + maxs.1) / 2.0,
+            (mins.2 + maxs.2) / 2.0,
+        );
+        let tip = Vector3::new(maxs.0 + (maxs.0 - mins.0) / 2.0, center.y, center.z);
+
+        let color = [
+            f32::from(self.color.0) / 255.0,
+            f32::from(self.color.1) / 255.0,
+            f32::from(self.color.2) / 255.0,
+        ];
+        corners.chain([center, tip]).map(move |x| Vertex {
+            position: (origin + rotation * x).into(),
+            color,
+        })
+    }
+}
+
+// Colors of classes without definition are stable between runs, so the same class looks the same
+pub(super) fn class_color(classname: &str) -> (u8, u8, u8) {
+    let hash = classname.bytes().fold(0x811c_9dc5u32, |hash, b| {
+        (hash ^ u32::from(b)).wrapping_mul(0x0100_0193)
+    });
+    let channel = |shift: u32| 64 + ((hash >> shift) & 0xFF) as u8 / 4 * 3;
+    (channel(0), channel(8), channel(16))
+}
+
+// `*` matches any characters and `?` a single one, ignoring case like classnames are matched
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.to_ascii_lowercase().into_bytes();
+    let name = name.to_ascii_lowercase().into_bytes();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+pub struct Markers {
+    markers: Vec<Marker>,
+    vbo: VertexBuffer<Vertex>,
+    ibo: IndexBuffer<u32>,
+    program: Program,
+    enabled: bool,
+}
+
+impl Markers {
+    // Point entities are ones placed by origin, brush entities have `*N` model
+    pub fn new<F: ?Sized + Facade>(facade: &F, entities: &Entities) -> Self {
+        let markers = entities
+            .entities()
+            .iter()
+            .filter(|e| !matches!(e.model(), Ok(Some(ModelRef::Brush(_)))))
+            .filter_map(|e| {
+                let classname = e.classname()?;
+                let origin = e.origin().ok().flatten()?;
+                Some(Marker {
+                    classname: classname.to_string(),
+                    origin,
+                    angles: e.angles().ok().flatten().unwrap_or_default(),
+                    size: DEFAULT_SIZE,
+                    color: class_color(classname),
+                    visible: true,
+                })
+            })
+            .collect();
+
+        let (elapsed, program) = measure_time(|| {
+            program!(facade,
+                140 => {
+                    vertex: include_str!("../../shaders/markers/vert.glsl"),
+                    fragment: include_str!("../../shaders/markers/frag.glsl"),
+                }
+            )
+            .unwrap()
+        });
+        debug!("Markers shader was loaded in {}", elapsed);
+
+        let mut markers = Self {
+            markers,
+            vbo: VertexBuffer::empty(facade, 0).unwrap(),
+            ibo: IndexBuffer::empty(facade, PrimitiveType::LinesList, 0).unwrap(),
+            program,
+            enabled: true,
+        };
+        markers.upload(facade);
+        info!("{} point entities", markers.markers.len());
+        markers
+    }
+
+    fn upload<F: ?Sized + Facade>(&mut self, facade: &F) {
+        let vertices: Vec<_> = self.markers.iter().flat_map(Marker::vertices).collect();
+        let indices: Vec<_> = self
+            .markers
+            .iter()
+            .zip((0..).step_by(VERTICES_PER_MARKER as usize))
+            .filter(|(marker, _)| marker.visible)
+            .flat_map(|(_, base): (_, u32)| {
+                BOX_EDGES
+                    .iter()
+                    .chain(&[(8, 9)])
+                    .flat_map(move |&(a, b)| [base + a, base + b])
+            })
+            .collect();
+        self.vbo = VertexBuffer::new(facade, &vertices).unwrap();
+        self.ibo = IndexBuffer::new(facade, PrimitiveType::LinesList, &indices).unwrap();
+    }
+
+    // Point classes of definitions give size and color, inherited from base classes too
+    pub fn set_definitions<F: ?Sized + Facade>(&mut self, facade: &F, fgd: &Fgd) {
+        for marker in &mut self.markers {
+            let class = fgd
+                .class(&marker.classname)
+                .filter(|class| class.ctype == ClassType::Point);
+            marker.size = class.and_then(|c| fgd.size(c)).unwrap_or(DEFAULT_SIZE);
+            marker.color = class
+                .and_then(|c| fgd.color(c))
+                .unwrap_or_else(|| class_color(&marker.classname));
+        }
+        self.upload(facade);
+    }
+
+    pub fn set_visible<F: ?Sized + Facade>(&mut self, facade: &F, pattern: &str, visible: bool) {
+        self.markers
+            .iter_mut()
+            .filter(|marker| matches_pattern(pattern, &marker.classname))
+            .for_each(|marker| marker.visible = visible);
+        self.upload(facade);
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub const fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn render<S: Surface>(
+        &self,
+        surface: &mut S,
+        mvp: [[f32; 4]; 4],
+        draw_params: &DrawParameters,
+    ) {
+        if !self.enabled || self.ibo.len() == 0 {
+            return;
+        }
+        let uniforms = uniform! {
+            mvp: mvp,
+        };
+        surface
+            .draw(&self.vbo, &self.ibo, &self.program, &uniforms, draw_params)
+            .unwrap();
+    }
+}
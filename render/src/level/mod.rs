@@ -1,76 +1,291 @@
+mod collision;
 mod entities;
+mod error;
 mod map;
+mod markers;
 mod skybox;
+mod volumes;
 
+use crate::texture::{TextureData, TextureSource};
 use cgmath::Matrix4;
+pub use error::Error;
 use file::{
-    bsp::{LumpType, RawMap},
+    bsp::{hull::Trace, lumps::parse_entities_str, LumpType, RawMap},
     cubemap::Cubemap,
+    detail::DetailTexture,
+    fgd::Fgd,
+    text::decode,
+    vfs::{FileSystem, GFX_DIR, MAPS_DIR},
     wad::Archive,
 };
 use glium::{backend::Facade, DrawParameters, Surface};
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use std::{fs::read as read_file, path::Path};
 use {
-    entities::{find_info_player_start, get_skyname, get_start_point, parse_entities, Vec3},
+    collision::Collision,
+    entities::{get_skyname, get_spawns, get_wad_names, parse_entities, Vec3},
     map::Map,
+    markers::Markers,
     skybox::Skybox,
+    volumes::Volumes,
 };
 
+pub use entities::Spawn;
+pub use volumes::VolumeStyle;
+
+const QUAKE_PALETTE_PATH: &str = "gfx/palette.lmp";
+const DETAIL_EXTENSION: &str = "tga";
+
+fn parse_archive<'a>(file: &'a [u8], palette: Option<&'a [u8]>) -> Option<Archive<'a>> {
+    match palette {
+        Some(palette) => Archive::parse_with_palette(file, palette).ok(),
+        None => Archive::parse(file).ok(),
+    }
+}
+
+fn load_details<F: ?Sized + Facade>(facade: &F, fs: &FileSystem, bsp_path: &Path, map: &mut Map) {
+    let map_name = match bsp_path.file_stem().and_then(|x| x.to_str()) {
+        Some(map_name) => map_name,
+        None => return,
+    };
+    let file_name = format!("{}_detail.txt", map_name);
+    let file = match read_file(bsp_path.with_file_name(&file_name))
+        .or_else(|_| fs.read(Path::new(MAPS_DIR).join(&file_name)))
+    {
+        Ok(file) => file,
+        Err(_) => return,
+    };
+
+    // Besides game directories, details are looked up near the map: `<game>/maps/x.bsp`
+    let game_dir = bsp_path.parent().and_then(Path::parent);
+    for detail in DetailTexture::parse_file(&decode(&file)) {
+        if map.find_texture_name(detail.texture).is_none() {
+            continue;
+        }
+        let relative = Path::new(GFX_DIR).join(format!("{}.{}", detail.detail, DETAIL_EXTENSION));
+        let image = fs
+            .resolve(&relative)
+            .or_else(|| game_dir.map(|dir| dir.join(&relative)))
+            .and_then(|path| image::open(path).ok());
+        match image {
+            Some(image) => map.set_detail(
+                facade,
+                detail.texture,
+                &TextureData::from_image(image.to_rgba8()),
+                detail.scale,
+            ),
+            None => warn!("Detail texture `{}` not found", detail.detail),
+        }
+    }
+    info!(
+        "{} detail textures loaded from {}",
+        map.details_num(),
+        file_name
+    );
+}
+
+fn report_duplicates(path: &Path, archive: &Archive) {
+    for name in archive.duplicates() {
+        warn!("Duplicate entry `{}` in {:?} is ignored", name, path);
+    }
+}
+
 pub struct Level {
-    start_point: Option<Vec3>,
+    spawns: Vec<(String, Spawn)>,
     map_render: Map,
+    markers: Markers,
+    collision: Collision,
+    volumes: Volumes,
     skybox: Option<Skybox>,
 }
 
 impl Level {
     pub fn new<F: ?Sized + Facade, P: AsRef<Path>>(
         facade: &F,
+        fs: &FileSystem,
         bsp_path: P,
         wad_paths: &[P],
         skybox_path: Option<P>,
-    ) -> Self {
-        // TODO : remove unwraps
-        let bsp_file = read_file(bsp_path).unwrap();
-        let raw_map = RawMap::parse(&bsp_file).unwrap();
-        let mut map_render = Map::new(facade, &raw_map);
+    ) -> Result<Self, Error> {
+        let bsp_path = fs.locate_map(bsp_path);
+        let bsp_path = bsp_path.as_path();
+        let bsp_file = read_file(bsp_path).map_err(|e| Error::Io(bsp_path.to_path_buf(), e))?;
+        let raw_map = RawMap::parse(&bsp_file).map_err(|_| Error::Parse("bsp header"))?;
+        let mut map_render = Map::new(facade, &raw_map)?;
+
+        let entities_str = parse_entities_str(raw_map.lump_data(LumpType::Entities))
+            .map_err(|_| Error::Parse("entities lump"))?;
+        let entities = parse_entities(&entities_str).ok_or(Error::Parse("entities lump"))?;
 
+        // Quake wads have no colors in miptex, they use the game palette
+        let quake_palette = fs.read(QUAKE_PALETTE_PATH).ok();
+
+        // Wads given by user go first, so they override ones the map was compiled with
         for path in wad_paths {
             if map_render.is_textures_loaded() {
                 break;
             }
-            let file = read_file(path).unwrap();
-            let archive = Archive::parse(&file).unwrap();
-            if let Some(file_name) = path.as_ref().file_name() {
-                debug!("Scanning {:?} for textures", file_name);
+            let path = fs.locate_wad(path);
+            let file = read_file(&path).map_err(|e| Error::Io(path.clone(), e))?;
+            let archive = parse_archive(&file, quake_palette.as_deref())
+                .ok_or(Error::Parse("wad archive"))?;
+            report_duplicates(&path, &archive);
+            debug!("Scanning {:?} for textures", path);
+            map_render.load_from_source(facade, &archive);
+        }
+        for name in get_wad_names(&entities) {
+            if map_render.is_textures_loaded() {
+                break;
+            }
+            let path = if let Some(path) = fs.find_wad(&name) {
+                path
+            } else {
+                warn!("Wad `{}` from worldspawn not found", name);
+                continue;
+            };
+            let file = match read_file(&path) {
+                Ok(file) => file,
+                Err(err) => {
+                    warn!("Error reading {:?}: {}", path, err);
+                    continue;
+                }
+            };
+            if let Some(archive) = parse_archive(&file, quake_palette.as_deref()) {
+                report_duplicates(&path, &archive);
+                debug!("Scanning {:?} for textures", path);
+                map_render.load_from_source(facade, &archive);
+            } else {
+                warn!("Error parsing wad {:?}", path);
             }
-            map_render.load_from_archive(facade, &archive);
         }
+        map_render.fill_missing_textures(facade);
+        load_details(facade, fs, bsp_path, &mut map_render);
 
-        let entities = parse_entities(raw_map.lump_data(LumpType::Entities)).unwrap();
-        let info_player_start = find_info_player_start(&entities);
-        let start_point = info_player_start.and_then(get_start_point);
+        let markers = Markers::new(facade, &entities);
+        let collision = Collision::new(&raw_map, &entities)?;
+        let volumes = Volumes::new(facade, &raw_map, &entities)?;
+        let spawns = get_spawns(&entities);
         let skybox = get_skyname(&entities).and_then(|skyname| {
-            skybox_path.and_then(|skybox_path| {
-                if let Ok(cubemap) = Cubemap::read(&skyname, skybox_path) {
-                    info!("Skybox loaded: {}", skyname);
-                    Some(Skybox::new(facade, &cubemap))
-                } else {
-                    error!("Error loading skybox: {}", skyname);
-                    None
-                }
-            })
+            let cubemap = match skybox_path {
+                Some(skybox_path) => Cubemap::read(&skyname, skybox_path),
+                None => Cubemap::read_fs(&skyname, fs),
+            };
+            if let Ok(cubemap) = cubemap {
+                info!("Skybox loaded: {}", skyname);
+                Some(Skybox::new(facade, &cubemap))
+            } else {
+                error!("Error loading skybox: {}", skyname);
+                None
+            }
         });
 
-        Self {
-            start_point,
+        Ok(Self {
+            spawns,
             map_render,
+            markers,
+            collision,
+            volumes,
             skybox,
-        }
+        })
+    }
+
+    // Textures still missing after loading (drawn as checkerboard) are looked up in the source
+    pub fn load_textures<F: ?Sized + Facade, T: ?Sized + TextureSource>(
+        &mut self,
+        facade: &F,
+        source: &T,
+    ) {
+        self.map_render.load_from_source(facade, source);
+    }
+
+    // Overrides textures found in the source, e.g. high-resolution replacements
+    pub fn replace_textures<F: ?Sized + Facade, T: ?Sized + TextureSource>(
+        &mut self,
+        facade: &F,
+        source: &T,
+    ) {
+        self.map_render.replace_from_source(facade, source);
+    }
+
+    pub fn set_details_enabled(&mut self, enabled: bool) {
+        self.map_render.set_details_enabled(enabled);
     }
 
-    pub const fn start_point(&self) -> Option<Vec3> {
-        self.start_point
+    pub const fn details_enabled(&self) -> bool {
+        self.map_render.details_enabled()
+    }
+
+    pub fn set_definitions<F: ?Sized + Facade>(&mut self, facade: &F, fgd: &Fgd) {
+        self.markers.set_definitions(facade, fgd);
+    }
+
+    // Point entities of classes matching pattern like `monster_*` are shown or hidden
+    pub fn set_markers_visible<F: ?Sized + Facade>(
+        &mut self,
+        facade: &F,
+        pattern: &str,
+        visible: bool,
+    ) {
+        self.markers.set_visible(facade, pattern, visible);
+    }
+
+    pub fn set_markers_enabled(&mut self, enabled: bool) {
+        self.markers.set_enabled(enabled);
+    }
+
+    pub const fn markers_enabled(&self) -> bool {
+        self.markers.enabled()
+    }
+
+    pub fn set_triggers_enabled(&mut self, enabled: bool) {
+        self.volumes.set_triggers_enabled(enabled);
+    }
+
+    pub const fn triggers_enabled(&self) -> bool {
+        self.volumes.triggers_enabled()
+    }
+
+    // Clip hull 1 (standing), 2 (large) or 3 (ducking) is built the first time it's shown
+    pub fn set_clip_hull<F: ?Sized + Facade>(&mut self, facade: &F, hull: Option<usize>) {
+        self.volumes.set_clip_hull(facade, &self.collision, hull);
+    }
+
+    pub const fn clip_hull(&self) -> Option<usize> {
+        self.volumes.clip_hull()
+    }
+
+    pub fn set_volume_style(&mut self, style: VolumeStyle) {
+        self.volumes.set_style(style);
+    }
+
+    pub const fn volume_style(&self) -> VolumeStyle {
+        self.volumes.style()
+    }
+
+    pub fn missing_textures(&self) -> impl Iterator<Item = &str> {
+        self.map_render.missing_textures()
+    }
+
+    // Moves a box of hull 1 (standing), 2 (large) or 3 (ducking) size through the world
+    // and solid brush entities, there is nothing to trace for other hulls
+    pub fn trace(&self, hull: usize, start: Vec3, end: Vec3) -> Option<Trace> {
+        self.collision.trace(hull, start, end)
+    }
+
+    fn spawns<'a>(&'a self, classname: &'a str) -> impl Iterator<Item = &'a Spawn> {
+        self.spawns
+            .iter()
+            .filter(move |(x, _)| x.eq_ignore_ascii_case(classname))
+            .map(|(_, spawn)| spawn)
+    }
+
+    // Entities of the class in order of the entities lump
+    pub fn spawn(&self, classname: &str, index: usize) -> Option<Spawn> {
+        self.spawns(classname).nth(index).copied()
+    }
+
+    pub fn spawns_num(&self, classname: &str) -> usize {
+        self.spawns(classname).count()
     }
 
     pub fn render<S: Surface>(
@@ -80,10 +295,23 @@ impl Level {
         view: Matrix4<f32>,
         draw_params: &DrawParameters,
     ) {
-        if let Some(skybox) = &self.skybox {
-            skybox.render(surface, projection, view, draw_params);
-        }
         self.map_render
             .render(surface, projection, view, draw_params);
+        self.markers
+            .render(surface, (projection * view).into(), draw_params);
+        if let (Some(skybox), Some(sky_ibo)) = (&self.skybox, self.map_render.sky_ibo()) {
+            skybox.render(
+                surface,
+                self.map_render.vbo(),
+                sky_ibo,
+                self.map_render.origin(),
+                projection,
+                view,
+                draw_params,
+            );
+        }
+        // Translucent volumes go last to blend over everything
+        self.volumes
+            .render(surface, (projection * view).into(), draw_params);
     }
 }
@@ -1,107 +1,19 @@
-use cgmath::{Matrix3, Matrix4};
+use cgmath::{Matrix4, SquareMatrix};
 use elapsed::measure_time;
 use file::cubemap::Cubemap as CubemapFile;
 use glium::{
     backend::Facade,
     framebuffer::SimpleFrameBuffer,
-    implement_vertex,
-    index::{IndexBuffer, IndexBufferAny, PrimitiveType},
+    index::IndexBufferAny,
     program,
     texture::{CubeLayer, Cubemap, RawImage2d, Texture2d},
     uniform,
     uniforms::MagnifySamplerFilter,
-    vertex::{VertexBuffer, VertexBufferAny},
+    vertex::VertexBufferAny,
     BlitTarget, DrawParameters, Program, Surface,
 };
 use log::debug;
 
-#[derive(Copy, Clone)]
-struct Vertex {
-    position: [f32; 3],
-}
-
-implement_vertex!(Vertex, position);
-
-const CUBE_VERTICES: [Vertex; 24] = [
-    Vertex {
-        position: [-1.0, -1.0, 1.0],
-    },
-    Vertex {
-        position: [1.0, -1.0, 1.0],
-    },
-    Vertex {
-        position: [1.0, 1.0, 1.0],
-    },
-    Vertex {
-        position: [-1.0, 1.0, 1.0],
-    },
-    Vertex {
-        position: [1.0, -1.0, 1.0],
-    },
-    Vertex {
-        position: [1.0, -1.0, -1.0],
-    },
-    Vertex {
-        position: [1.0, 1.0, -1.0],
-    },
-    Vertex {
-        position: [1.0, 1.0, 1.0],
-    },
-    Vertex {
-        position: [-1.0, -1.0, -1.0],
-    },
-    Vertex {
-        position: [-1.0, 1.0, -1.0],
-    },
-    Vertex {
-        position: [1.0, 1.0, -1.0],
-    },
-    Vertex {
-        position: [1.0, -1.0, -1.0],
-    },
-    Vertex {
-        position: [-1.0, -1.0, 1.0],
-    },
-    Vertex {
-        position: [-1.0, 1.0, 1.0],
-    },
-    Vertex {
-        position: [-1.0, 1.0, -1.0],
-    },
-    Vertex {
-        position: [-1.0, -1.0, -1.0],
-    },
-    Vertex {
-        position: [-1.0, -1.0, 1.0],
-    },
-    Vertex {
-        position: [-1.0, -1.0, -1.0],
-    },
-    Vertex {
-        position: [1.0, -1.0, -1.0],
-    },
-    Vertex {
-        position: [1.0, -1.0, 1.0],
-    },
-    Vertex {
-        position: [-1.0, 1.0, 1.0],
-    },
-    Vertex {
-        position: [1.0, 1.0, 1.0],
-    },
-    Vertex {
-        position: [1.0, 1.0, -1.0],
-    },
-    Vertex {
-        position: [-1.0, 1.0, -1.0],
-    },
-];
-
-const CUBE_INDICES: [u16; 36] = [
-    1, 2, 0, 2, 3, 0, 5, 6, 4, 6, 7, 4, 9, 10, 8, 10, 11, 8, 13, 14, 12, 14, 15, 12, 17, 18, 16,
-    18, 19, 16, 21, 22, 20, 22, 23, 20,
-];
-
 const CUBEMAP_SIDES: [CubeLayer; 6] = [
     CubeLayer::PositiveX,
     CubeLayer::NegativeX,
@@ -112,17 +24,12 @@ const CUBEMAP_SIDES: [CubeLayer; 6] = [
 ];
 
 pub struct Skybox {
-    vbo: VertexBufferAny,
-    ibo: IndexBufferAny,
     cubemap: Cubemap,
     program: Program,
 }
 
 impl Skybox {
     pub fn new<F: ?Sized + Facade>(facade: &F, cubemap_file: &CubemapFile) -> Self {
-        let vbo = VertexBuffer::new(facade, &CUBE_VERTICES).unwrap();
-        let ibo = IndexBuffer::new(facade, PrimitiveType::TrianglesList, &CUBE_INDICES).unwrap();
-
         let (elapsed, program) = measure_time(|| {
             program!(facade,
                 140 => {
@@ -161,32 +68,36 @@ impl Skybox {
             debug!("{:?} was loaded in {}", side, elapsed);
         }
 
-        Self {
-            vbo: vbo.into(),
-            ibo: ibo.into(),
-            program,
-            cubemap,
-        }
+        Self { program, cubemap }
     }
 
+    // Sky faces are drawn as portals: they write depth like any other face,
+    // but the colour is taken from the cubemap by view direction
+    #[allow(clippy::too_many_arguments)]
     pub fn render<S: Surface>(
         &self,
         surface: &mut S,
+        vbo: &VertexBufferAny,
+        ibo: &IndexBufferAny,
+        origin: [f32; 3],
         projection: Matrix4<f32>,
         view: Matrix4<f32>,
         draw_params: &DrawParameters,
     ) {
-        let view = Matrix3::from_cols(view.x.truncate(), view.y.truncate(), view.z.truncate());
-        let view = Matrix4::from(view);
+        let eye = view
+            .invert()
+            .map_or([0.0; 3], |inv| inv.w.truncate().into());
         let mvp = projection * view;
         let mvp: [[f32; 4]; 4] = mvp.into();
 
         let uniforms = uniform! {
             mvp: mvp,
+            origin: origin,
+            eye: eye,
             cubetex: self.cubemap.sampled().magnify_filter(MagnifySamplerFilter::Linear),
         };
         surface
-            .draw(&self.vbo, &self.ibo, &self.program, &uniforms, draw_params)
+            .draw(vbo, ibo, &self.program, &uniforms, draw_params)
             .unwrap();
     }
 }
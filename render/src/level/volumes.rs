@@ -0,0 +1,308 @@
+use super::{
+    collision::{is_trigger, Collision},
+    markers::class_color,
+    Error,
+};
+use elapsed::measure_time;
+use file::{
+    bsp::{hull::HULL_SIZES, lumps::*, LumpType, RawMap},
+    map::{Entities, ModelRef},
+};
+use glium::{
+    backend::Facade,
+    draw_parameters::{BackfaceCullingMode, Blend, Depth},
+    implement_vertex,
+    index::{IndexBuffer, PrimitiveType},
+    program, uniform,
+    vertex::VertexBuffer,
+    DrawParameters, Program, Surface,
+};
+use log::{debug, info};
+
+const TRIGGER_ALPHA: f32 = 0.3;
+const HULL_ALPHA: f32 = 0.4;
+const HULL_COLORS: [[f32; 3]; 3] = [[0.2, 0.8, 1.0], [1.0, 0.3, 0.9], [1.0, 0.9, 0.2]];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VolumeStyle {
+    Wireframe,
+    Translucent,
+}
+
+#[derive(Copy, Clone)]
+struct Vertex {
+    position: [f32; 3],
+    normal: [f32; 3],
+    color: [f32; 3],
+}
+
+implement_vertex!(Vertex, position, normal, color);
+
+type Polygon = (Vec<Vec3>, [f32; 3]); // vertices and color
+
+fn normal(polygon: &[Vec3]) -> [f32; 3] {
+    let (a, b, c) = (polygon[0], polygon[1], polygon[2]);
+    let (u, v) = (
+        (b.0 - a.0, b.1 - a.1, b.2 - a.2),
+        (c.0 - a.0, c.1 - a.1, c.2 - a.2),
+    );
+    let n = (
+        u.1 * v.2 - u.2 * v.1,
+        u.2 * v.0 - u.0 * v.2,
+        u.0 * v.1 - u.1 * v.0,
+    );
+    let length = (n.0 * n.0 + n.1 * n.1 + n.2 * n.2).sqrt().max(f32::EPSILON);
+    [n.0 / length, n.1 / length, n.2 / length]
+}
+
+// Polygons are drawn as triangle fans or by their edges
+struct Mesh {
+    vbo: VertexBuffer<Vertex>,
+    triangles: IndexBuffer<u32>,
+    lines: IndexBuffer<u32>,
+}
+
+impl Mesh {
+    fn new<F: ?Sized + Facade>(facade: &F, polygons: &[Polygon]) -> Self {
+        let mut vertices = Vec::new();
+        let mut triangles = Vec::new();
+        let mut lines = Vec::new();
+        for (polygon, color) in polygons.iter().filter(|(x, _)| x.len() >= 3) {
+            let base = vertices.len() as u32;
+            let n = polygon.len() as u32;
+            let normal = normal(polygon);
+            vertices.extend(polygon.iter().map(|v| Vertex {
+                position: [v.0, v.1, v.2],
+                normal,
+                color: *color,
+            }));
+            triangles.extend((1..n - 1).flat_map(|i| [base, base + i, base + i + 1]));
+            lines.extend((0..n).flat_map(|i| [base + i, base + (i + 1) % n]));
+        }
+        Self {
+            vbo: VertexBuffer::new(facade, &vertices).unwrap(),
+            triangles: IndexBuffer::new(facade, PrimitiveType::TrianglesList, &triangles).unwrap(),
+            lines: IndexBuffer::new(facade, PrimitiveType::LinesList, &lines).unwrap(),
+        }
+    }
+}
+
+// Faces of hull 0 of brush models, offset by origin like the world is
+fn models_polygons(map: &RawMap, models: &[(Model, [f32; 3])]) -> Result<Vec<Polygon>, Error> {
+    let vertices = parse_vertices(map.lump_data(LumpType::Vertices))
+        .map_err(|_| Error::Parse("vertices lump"))?;
+    let edges =
+        parse_edges(map.lump_data(LumpType::Edges)).map_err(|_| Error::Parse("edges lump"))?;
+    let surfedges = parse_surfedges(map.lump_data(LumpType::Surfegdes))
+        .map_err(|_| Error::Parse("surfedges lump"))?;
+    let faces =
+        parse_faces(map.lump_data(LumpType::Faces)).map_err(|_| Error::Parse("faces lump"))?;
+
+    models
+        .iter()
+        .flat_map(|(model, color)| {
+            faces
+                .iter()
+                .skip(model.face_id)
+                .take(model.face_num)
+                .map(move |face| (model.origin, face, *color))
+        })
+        .map(|(o, face, color)| {
+            let polygon = surfedges
+                .iter()
+                .skip(face.surfedge_id)
+                .take(face.surfedge_num)
+                .map(|&s| {
+                    let edge = edges
+                        .get(s.unsigned_abs() as usize)
+                        .ok_or(Error::Parse("surfedges lump"))?;
+                    let i = if s < 0 { edge.1 } else { edge.0 } as usize;
+                    let v = vertices.get(i).ok_or(Error::Parse("edges lump"))?;
+                    Ok((v.0 + o.0, v.1 + o.1, v.2 + o.2))
+                })
+                .collect::<Result<_, Error>>()?;
+            Ok((polygon, color))
+        })
+        .collect()
+}
+
+// Trigger brush entities and clip hulls, hidden in the game but useful to check a map
+pub struct Volumes {
+    triggers: Mesh,
+    hulls: [Option<Mesh>; 3], // built when shown first time
+    program: Program,
+    triggers_enabled: bool,
+    clip_hull: Option<usize>,
+    style: VolumeStyle,
+}
+
+impl Volumes {
+    pub fn new<F: ?Sized + Facade>(
+        facade: &F,
+        map: &RawMap,
+        entities: &Entities,
+    ) -> Result<Self, Error> {
+        let mut models = parse_models(map.lump_data(LumpType::Models))
+            .map_err(|_| Error::Parse("models lump"))?
+            .into_iter()
+            .map(Some)
+            .collect::<Vec<_>>();
+
+        let mut triggers = Vec::new();
+        for entity in entities.entities() {
+            let classname = entity.classname().unwrap_or_default();
+            let index = match entity.model() {
+                Ok(Some(ModelRef::Brush(index))) if index > 0 => index,
+                _ => continue,
+            };
+            if !is_trigger(classname) {
+                continue;
+            }
+            if let Some(model) = models.get_mut(index).and_then(Option::take) {
+                let (r, g, b) = class_color(classname);
+                let color = [
+                    f32::from(r) / 255.0,
+                    f32::from(g) / 255.0,
+                    f32::from(b) / 255.0,
+                ];
+                triggers.push((model, color));
+            }
+        }
+
+        let (elapsed, program) = measure_time(|| {
+            program!(facade,
+                140 => {
+                    vertex: include_str!("../../shaders/volumes/vert.glsl"),
+                    fragment: include_str!("../../shaders/volumes/frag.glsl"),
+                }
+            )
+            .unwrap()
+        });
+        debug!("Volumes shader was loaded in {}", elapsed);
+
+        let triggers = models_polygons(map, &triggers)?;
+        info!("{} trigger faces", triggers.len());
+        Ok(Self {
+            triggers: Mesh::new(facade, &triggers),
+            hulls: [None, None, None],
+            program,
+            triggers_enabled: false,
+            clip_hull: None,
+            style: VolumeStyle::Wireframe,
+        })
+    }
+
+    fn build_hull<F: ?Sized + Facade>(facade: &F, collision: &Collision, hull: usize) -> Mesh {
+        let (elapsed, polygons) = measure_time(|| {
+            collision
+                .polygons(hull)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|polygon| (polygon, HULL_COLORS[hull - 1]))
+                .collect::<Vec<_>>()
+        });
+        info!(
+            "Clip hull {} was built in {}: {} faces",
+            hull,
+            elapsed,
+            polygons.len()
+        );
+        Mesh::new(facade, &polygons)
+    }
+
+    pub fn set_triggers_enabled(&mut self, enabled: bool) {
+        self.triggers_enabled = enabled;
+    }
+
+    pub const fn triggers_enabled(&self) -> bool {
+        self.triggers_enabled
+    }
+
+    // Hulls 1-3 can be shown, hull 0 is the map itself
+    pub fn set_clip_hull<F: ?Sized + Facade>(
+        &mut self,
+        facade: &F,
+        collision: &Collision,
+        hull: Option<usize>,
+    ) {
+        let hull = hull.filter(|x| (1..HULL_SIZES.len()).contains(x));
+        if let Some(hull) = hull {
+            if self.hulls[hull - 1].is_none() {
+                self.hulls[hull - 1] = Some(Self::build_hull(facade, collision, hull));
+            }
+        }
+        self.clip_hull = hull;
+    }
+
+    pub const fn clip_hull(&self) -> Option<usize> {
+        self.clip_hull
+    }
+
+    pub fn set_style(&mut self, style: VolumeStyle) {
+        self.style = style;
+    }
+
+    pub const fn style(&self) -> VolumeStyle {
+        self.style
+    }
+
+    fn draw<S: Surface>(
+        &self,
+        surface: &mut S,
+        mesh: &Mesh,
+        style: VolumeStyle,
+        alpha: f32,
+        mvp: [[f32; 4]; 4],
+        draw_params: &DrawParameters,
+    ) {
+        // Translucent faces are seen from both sides and don't hide each other
+        let draw_params = DrawParameters {
+            blend: Blend::alpha_blending(),
+            backface_culling: BackfaceCullingMode::CullingDisabled,
+            depth: Depth {
+                write: false,
+                ..draw_params.depth
+            },
+            ..draw_params.clone()
+        };
+        let (ibo, alpha) = match style {
+            VolumeStyle::Wireframe => (&mesh.lines, 1.0),
+            VolumeStyle::Translucent => (&mesh.triangles, alpha),
+        };
+        if ibo.len() == 0 {
+            return;
+        }
+        let uniforms = uniform! {
+            mvp: mvp,
+            alpha: alpha,
+        };
+        surface
+            .draw(&mesh.vbo, ibo, &self.program, &uniforms, &draw_params)
+            .unwrap();
+    }
+
+    pub fn render<S: Surface>(
+        &self,
+        surface: &mut S,
+        mvp: [[f32; 4]; 4],
+        draw_params: &DrawParameters,
+    ) {
+        if let Some(mesh) = self
+            .clip_hull
+            .and_then(|hull| self.hulls[hull - 1].as_ref())
+        {
+            self.draw(surface, mesh, self.style, HULL_ALPHA, mvp, draw_params);
+        }
+        if self.triggers_enabled {
+            let mesh = &self.triggers;
+            self.draw(
+                surface,
+                mesh,
+                VolumeStyle::Translucent,
+                TRIGGER_ALPHA,
+                mvp,
+                draw_params,
+            );
+        }
+    }
+}
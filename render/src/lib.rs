@@ -1,3 +1,5 @@
 mod level;
+pub mod texture;
+pub mod walk;
 
-pub use level::Level; // to reduce repetition of names
+pub use level::{Error, Level, Spawn, VolumeStyle}; // to reduce repetition of names
@@ -0,0 +1,150 @@
+use file::{
+    miptex::MipTexture,
+    wad::{Archive, Content},
+};
+use image::{imageops::FilterType, RgbaImage};
+use std::path::{Path, PathBuf};
+
+const MIP_LEVELS: usize = 4;
+const IMAGE_EXTENSIONS: [&str; 2] = ["png", "tga"];
+
+// RGBA pixels of every mip level, the first one is the full size
+pub struct TextureData {
+    pub width: u32,
+    pub height: u32,
+    pub mip_levels: Vec<Vec<u8>>,
+}
+
+impl TextureData {
+    pub fn from_miptex(miptex: &MipTexture) -> Option<Self> {
+        let mip_levels = (0..MipTexture::layers())
+            .map(|i| miptex.pixels(i))
+            .collect::<Option<_>>()?;
+        Some(Self {
+            width: miptex.main_width(),
+            height: miptex.main_height(),
+            mip_levels,
+        })
+    }
+
+    pub fn from_image(image: RgbaImage) -> Self {
+        let (width, height) = image.dimensions();
+        let mut mip_levels = vec![];
+        let mut level = image;
+        loop {
+            let (w, h) = level.dimensions();
+            let next = (w > 1 || h > 1).then(|| {
+                image::imageops::resize(
+                    &level,
+                    (w / 2).max(1),
+                    (h / 2).max(1),
+                    FilterType::Triangle,
+                )
+            });
+            mip_levels.push(level.into_raw());
+            match next {
+                Some(next) if mip_levels.len() < MIP_LEVELS => level = next,
+                _ => break,
+            }
+        }
+        Self {
+            width,
+            height,
+            mip_levels,
+        }
+    }
+}
+
+pub trait TextureSource {
+    // Names are matched ignoring case, like the engine does
+    fn find(&self, name: &str) -> Option<TextureData>;
+}
+
+impl TextureSource for Archive<'_> {
+    fn find(&self, name: &str) -> Option<TextureData> {
+        match self.get_by_name(name)?.decode().ok()? {
+            Content::MipTexture(miptex) => TextureData::from_miptex(&miptex),
+            _ => None,
+        }
+    }
+}
+
+// Textures embedded into the bsp
+impl TextureSource for [MipTexture<'_>] {
+    fn find(&self, name: &str) -> Option<TextureData> {
+        self.iter()
+            .filter(|miptex| !miptex.is_empty())
+            .find(|miptex| miptex.name().eq_ignore_ascii_case(name))
+            .and_then(TextureData::from_miptex)
+    }
+}
+
+// Loose `<name>.png` or `<name>.tga` files
+pub struct ImageDirectory {
+    dir: PathBuf,
+}
+
+impl ImageDirectory {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn find_file(&self, name: &str) -> Option<PathBuf> {
+        let candidates = IMAGE_EXTENSIONS
+            .iter()
+            .map(|ext| format!("{}.{}", name, ext))
+            .collect::<Vec<_>>();
+        self.dir.read_dir().ok()?.flatten().find_map(|entry| {
+            let file_name = entry.file_name();
+            let file_name = file_name.to_str()?;
+            candidates
+                .iter()
+                .any(|x| x.eq_ignore_ascii_case(file_name))
+                .then(|| entry.path())
+        })
+    }
+}
+
+impl TextureSource for ImageDirectory {
+    fn find(&self, name: &str) -> Option<TextureData> {
+        let image = image::open(self.find_file(name)?).ok()?;
+        Some(TextureData::from_image(image.to_rgba8()))
+    }
+}
+
+// Sources are asked in order they were added, the first one has the highest priority
+#[derive(Default)]
+pub struct TextureChain<'a> {
+    sources: Vec<Box<dyn TextureSource + 'a>>,
+}
+
+impl<'a> TextureChain<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push<T: TextureSource + 'a>(&mut self, source: T) {
+        self.sources.push(Box::new(source));
+    }
+
+    pub fn with<T: TextureSource + 'a>(mut self, source: T) -> Self {
+        self.push(source);
+        self
+    }
+}
+
+impl TextureSource for TextureChain<'_> {
+    fn find(&self, name: &str) -> Option<TextureData> {
+        self.sources.iter().find_map(|source| source.find(name))
+    }
+}
+
+impl<T: TextureSource + ?Sized> TextureSource for &T {
+    fn find(&self, name: &str) -> Option<TextureData> {
+        (**self).find(name)
+    }
+}
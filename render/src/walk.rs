@@ -0,0 +1,304 @@
+use cgmath::{InnerSpace, Vector3, Zero};
+use file::bsp::{hull::Trace, lumps::Vec3};
+
+// Movement settings of the game, `sv_*` cvars and `pm_shared` constants
+const GRAVITY: f32 = 800.0;
+const MAX_SPEED: f32 = 320.0;
+const ACCELERATE: f32 = 10.0;
+const AIR_ACCELERATE: f32 = 10.0;
+const AIR_MAX_SPEED: f32 = 30.0;
+const FRICTION: f32 = 4.0;
+const STOP_SPEED: f32 = 100.0;
+const JUMP_SPEED: f32 = 268.3; // sqrt(2 * 800 * 45), so jumps are 45 units high
+const DUCK_SPEED_FACTOR: f32 = 0.333;
+const STEP_SIZE: f32 = 18.0;
+const GROUND_DISTANCE: f32 = 2.0;
+const MIN_GROUND_NORMAL_Z: f32 = 0.7; // steeper planes are walls
+const MAX_FALL_SPEED_ON_GROUND: f32 = 180.0; // rising faster leaves the ground
+const MAX_CLIP_PLANES: usize = 5;
+const MOVE_BUMPS: usize = 4;
+
+const STAND_HULL: usize = 1;
+const DUCK_HULL: usize = 3;
+const DUCK_HEIGHT_CHANGE: f32 = 18.0; // between centers of hull 1 and hull 3 with the same feet
+pub const STAND_VIEW_OFFSET: f32 = 28.0; // `VEC_VIEW`
+const DUCK_VIEW_OFFSET: f32 = 12.0; // `VEC_DUCK_VIEW`
+
+fn horizontal_distance(a: Vector3<f32>, b: Vector3<f32>) -> f32 {
+    (a - b).truncate().magnitude()
+}
+
+// Removes the part of velocity going into the plane, so the player slides along it
+fn clip_velocity(velocity: Vector3<f32>, normal: Vector3<f32>) -> Vector3<f32> {
+    velocity - normal * velocity.dot(normal)
+}
+
+// Movement keys held at the moment
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Input {
+    pub forward: bool,
+    pub back: bool,
+    pub left: bool,
+    pub right: bool,
+    pub jump: bool,
+    pub duck: bool,
+}
+
+// Box of player size moved by the clip hulls, unlike the noclip camera.
+// Collision comes from `trace`, which moves a box of the hull size from start to end.
+pub struct Player {
+    origin: Vector3<f32>, // center of the hull
+    velocity: Vector3<f32>,
+    ducked: bool,
+    on_ground: bool,
+    jump_held: bool, // jumping again needs the key released
+}
+
+impl Player {
+    // Ducking is tried if there is no room to stand, e.g. in a vent
+    pub fn new<T: Fn(usize, Vec3, Vec3) -> Trace>(trace: &T, eyes: Vec3) -> Self {
+        let eyes = Vector3::from(eyes);
+        let standing = eyes - Vector3::unit_z() * STAND_VIEW_OFFSET;
+        let ducking = eyes - Vector3::unit_z() * DUCK_VIEW_OFFSET;
+        let ducked = trace(STAND_HULL, standing.into(), standing.into()).start_solid
+            && !trace(DUCK_HULL, ducking.into(), ducking.into()).start_solid;
+        Self {
+            origin: if ducked { ducking } else { standing },
+            velocity: Vector3::zero(),
+            ducked,
+            on_ground: false,
+            jump_held: false,
+        }
+    }
+
+    pub fn eyes(&self) -> Vec3 {
+        let offset = if self.ducked {
+            DUCK_VIEW_OFFSET
+        } else {
+            STAND_VIEW_OFFSET
+        };
+        (self.origin + Vector3::unit_z() * offset).into()
+    }
+
+    pub fn origin(&self) -> Vec3 {
+        self.origin.into()
+    }
+
+    pub fn velocity(&self) -> Vec3 {
+        self.velocity.into()
+    }
+
+    pub const fn ducked(&self) -> bool {
+        self.ducked
+    }
+
+    pub const fn on_ground(&self) -> bool {
+        self.on_ground
+    }
+
+    const fn hull(&self) -> usize {
+        if self.ducked {
+            DUCK_HULL
+        } else {
+            STAND_HULL
+        }
+    }
+
+    fn trace<T: Fn(usize, Vec3, Vec3) -> Trace>(&self, trace: &T, end: Vector3<f32>) -> Trace {
+        trace(self.hull(), self.origin.into(), end.into())
+    }
+
+    // Yaw is in degrees counterclockwise from X axis like Quake angles
+    pub fn update<T: Fn(usize, Vec3, Vec3) -> Trace>(
+        &mut self,
+        trace: &T,
+        input: &Input,
+        yaw: f32,
+        dt: f32,
+    ) {
+        self.duck(trace, input.duck);
+        self.categorize_position(trace);
+
+        if input.jump && !self.jump_held && self.on_ground {
+            self.velocity.z = JUMP_SPEED;
+            self.on_ground = false;
+        }
+        self.jump_held = input.jump;
+
+        let axis = |positive: bool, negative: bool| {
+            f32::from(i8::from(positive) - i8::from(negative)) * MAX_SPEED
+        };
+        let (sin, cos) = yaw.to_radians().sin_cos();
+        let (forward, right) = (Vector3::new(cos, sin, 0.0), Vector3::new(sin, -cos, 0.0));
+        let wish_velocity =
+            forward * axis(input.forward, input.back) + right * axis(input.right, input.left);
+        let mut wish_speed = wish_velocity.magnitude();
+        let wish_dir = if wish_speed > 0.0 {
+            wish_velocity / wish_speed
+        } else {
+            Vector3::zero()
+        };
+        wish_speed = wish_speed.min(MAX_SPEED);
+        if self.ducked {
+            wish_speed *= DUCK_SPEED_FACTOR;
+        }
+
+        if self.on_ground {
+            self.friction(dt);
+            self.accelerate(wish_dir, wish_speed, wish_speed, ACCELERATE, dt);
+            self.velocity.z = 0.0;
+            self.walk_move(trace, dt);
+        } else {
+            self.accelerate(wish_dir, wish_speed, AIR_MAX_SPEED, AIR_ACCELERATE, dt);
+            self.velocity.z -= GRAVITY * dt;
+            self.fly_move(trace, dt);
+        }
+
+        self.categorize_position(trace);
+        if self.on_ground {
+            self.velocity.z = 0.0;
+        }
+    }
+
+    // Feet stay in place ducking on the ground, in the air legs are pulled up instead
+    fn duck<T: Fn(usize, Vec3, Vec3) -> Trace>(&mut self, trace: &T, duck: bool) {
+        let shift = if self.on_ground {
+            DUCK_HEIGHT_CHANGE
+        } else {
+            -DUCK_HEIGHT_CHANGE
+        };
+        if duck && !self.ducked {
+            self.origin.z -= shift;
+            self.ducked = true;
+        } else if !duck && self.ducked {
+            let origin = self.origin + Vector3::unit_z() * shift;
+            if !trace(STAND_HULL, origin.into(), origin.into()).start_solid {
+                self.origin = origin;
+                self.ducked = false;
+            }
+        }
+    }
+
+    // Ground is a walkable plane right under the feet, the player is snapped down onto it
+    fn categorize_position<T: Fn(usize, Vec3, Vec3) -> Trace>(&mut self, trace: &T) {
+        if self.velocity.z > MAX_FALL_SPEED_ON_GROUND {
+            self.on_ground = false;
+            return;
+        }
+        let below = self.origin - Vector3::unit_z() * GROUND_DISTANCE;
+        let ground = self.trace(trace, below);
+        self.on_ground = ground.fraction < 1.0 && ground.normal.2 >= MIN_GROUND_NORMAL_Z;
+        if self.on_ground && !ground.start_solid && !ground.all_solid {
+            self.origin = ground.end.into();
+        }
+    }
+
+    fn friction(&mut self, dt: f32) {
+        let speed = self.velocity.magnitude();
+        if speed < 0.1 {
+            return;
+        }
+        let drop = speed.max(STOP_SPEED) * FRICTION * dt;
+        self.velocity *= (speed - drop).max(0.0) / speed;
+    }
+
+    fn accelerate(
+        &mut self,
+        wish_dir: Vector3<f32>,
+        wish_speed: f32,
+        max_speed: f32,
+        accelerate: f32,
+        dt: f32,
+    ) {
+        let add_speed = wish_speed.min(max_speed) - self.velocity.dot(wish_dir);
+        if add_speed <= 0.0 {
+            return;
+        }
+        let speed = (accelerate * wish_speed * dt).min(add_speed);
+        self.velocity += wish_dir * speed;
+    }
+
+    // Moves along the velocity, sliding along everything hit, the way `PM_FlyMove` does
+    fn fly_move<T: Fn(usize, Vec3, Vec3) -> Trace>(&mut self, trace: &T, dt: f32) {
+        let primal_velocity = self.velocity;
+        let mut original_velocity = self.velocity;
+        let mut planes: Vec<Vector3<f32>> = Vec::with_capacity(MAX_CLIP_PLANES);
+        let mut time_left = dt;
+
+        for _ in 0..MOVE_BUMPS {
+            if self.velocity.is_zero() {
+                break;
+            }
+            let hit = self.trace(trace, self.origin + self.velocity * time_left);
+            if hit.all_solid {
+                self.velocity = Vector3::zero(); // stuck, noclip gets out
+                return;
+            }
+            if hit.fraction > 0.0 {
+                self.origin = hit.end.into();
+                original_velocity = self.velocity;
+                planes.clear();
+            }
+            if hit.fraction >= 1.0 {
+                break;
+            }
+            time_left -= time_left * hit.fraction;
+            if planes.len() >= MAX_CLIP_PLANES {
+                self.velocity = Vector3::zero();
+                break;
+            }
+            planes.push(hit.normal.into());
+
+            // Velocity going away from all planes, or along the crease of two of them
+            let slide = planes
+                .iter()
+                .map(|&plane| clip_velocity(original_velocity, plane))
+                .find(|velocity| planes.iter().all(|&plane| velocity.dot(plane) >= -0.001));
+            self.velocity = match (slide, &planes[..]) {
+                (Some(velocity), _) => velocity,
+                (None, &[a, b]) => {
+                    let dir = a.cross(b);
+                    dir * dir.dot(self.velocity)
+                }
+                (None, _) => Vector3::zero(),
+            };
+            // Turning back would make the player shake in corners
+            if self.velocity.dot(primal_velocity) <= 0.0 {
+                self.velocity = Vector3::zero();
+                break;
+            }
+        }
+    }
+
+    // Moves on the ground, going up stairs if it gets farther than sliding along them.
+    // Stepping off into the air keeps the plain move, so the player falls from ledges.
+    fn walk_move<T: Fn(usize, Vec3, Vec3) -> Trace>(&mut self, trace: &T, dt: f32) {
+        let (start_origin, start_velocity) = (self.origin, self.velocity);
+        self.fly_move(trace, dt);
+        let (down_origin, down_velocity) = (self.origin, self.velocity);
+
+        self.origin = start_origin;
+        self.velocity = start_velocity;
+        let up = self.trace(trace, start_origin + Vector3::unit_z() * STEP_SIZE);
+        if !up.start_solid && !up.all_solid {
+            self.origin = up.end.into();
+        }
+        self.fly_move(trace, dt);
+
+        let down = self.trace(trace, self.origin - Vector3::unit_z() * STEP_SIZE);
+        let down_end = Vector3::from(down.end);
+        let step_up = !down.start_solid
+            && !down.all_solid
+            && down.fraction < 1.0
+            && down.normal.2 >= MIN_GROUND_NORMAL_Z
+            && horizontal_distance(down_end, start_origin)
+                > horizontal_distance(down_origin, start_origin);
+        if step_up {
+            self.origin = down_end;
+            self.velocity.z = down_velocity.z;
+        } else {
+            self.origin = down_origin;
+            self.velocity = down_velocity;
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use image::RgbaImage;
+use render::texture::{ImageDirectory, TextureChain, TextureData, TextureSource};
+
+struct Solid(&'static str, u8);
+
+impl TextureSource for Solid {
+    fn find(&self, name: &str) -> Option<TextureData> {
+        name.eq_ignore_ascii_case(self.0)
+            .then(|| TextureData::from_image(RgbaImage::from_pixel(8, 8, [self.1; 4].into())))
+    }
+}
+
+#[test]
+fn chain_respects_priority() {
+    let dir =
+        std::env::temp_dir().join(format!("hlbsp_texture_source_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    RgbaImage::from_pixel(16, 4, [1, 2, 3, 255].into())
+        .save(dir.join("CRATE01.png"))
+        .unwrap();
+
+    let chain = TextureChain::new()
+        .with(ImageDirectory::new(&dir))
+        .with(Solid("crate01", 10))
+        .with(Solid("wall", 20));
+
+    let crate01 = chain.find("crate01").unwrap();
+    assert_eq!((crate01.width, crate01.height), (16, 4));
+    assert_eq!(crate01.mip_levels.len(), 4);
+    assert_eq!(crate01.mip_levels[3].len(), 2 * 4); // 2x1 texels
+    assert_eq!(chain.find("WALL").unwrap().mip_levels[0][0], 20);
+    assert!(chain.find("missing").is_none());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
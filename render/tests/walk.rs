@@ -0,0 +1,159 @@
+use file::bsp::{
+    hull::{Hull, Trace, CONTENTS_EMPTY, CONTENTS_SOLID, HULL_SIZES},
+    lumps::{Clipnode, Plane, Vec3},
+};
+use render::walk::{Input, Player, STAND_VIEW_OFFSET};
+
+const DT: f32 = 0.01;
+
+// Solid boxes given by mins and maxs, clip hulls are the boxes expanded by the hull size.
+// Nodes of each box go +x, -x, +y, -y, +z, -z, points in front of any go to the next box.
+struct World {
+    hulls: Vec<(Vec<Plane>, Vec<Clipnode>)>,
+}
+
+impl World {
+    fn new(boxes: &[(Vec3, Vec3)]) -> Self {
+        let hulls = HULL_SIZES
+            .iter()
+            .map(|&(size_mins, size_maxs)| {
+                let mut planes = Vec::new();
+                let mut clipnodes = Vec::new();
+                for (i, &(mins, maxs)) in boxes.iter().enumerate() {
+                    let sides = [
+                        ((1.0, 0.0, 0.0), maxs.0 - size_mins.0),
+                        ((-1.0, 0.0, 0.0), size_maxs.0 - mins.0),
+                        ((0.0, 1.0, 0.0), maxs.1 - size_mins.1),
+                        ((0.0, -1.0, 0.0), size_maxs.1 - mins.1),
+                        ((0.0, 0.0, 1.0), maxs.2 - size_mins.2),
+                        ((0.0, 0.0, -1.0), size_maxs.2 - mins.2),
+                    ];
+                    let next_box = if i + 1 < boxes.len() {
+                        (i as i32 + 1) * 6
+                    } else {
+                        CONTENTS_EMPTY
+                    };
+                    for (j, &(normal, dist)) in sides.iter().enumerate() {
+                        clipnodes.push(Clipnode {
+                            plane_id: planes.len(),
+                            children: [
+                                next_box,
+                                if j == 5 {
+                                    CONTENTS_SOLID
+                                } else {
+                                    clipnodes.len() as i32 + 1
+                                },
+                            ],
+                        });
+                        planes.push(Plane { normal, dist });
+                    }
+                }
+                (planes, clipnodes)
+            })
+            .collect();
+        Self { hulls }
+    }
+
+    fn trace(&self, hull: usize, start: Vec3, end: Vec3) -> Trace {
+        let (planes, clipnodes) = &self.hulls[hull];
+        Hull::new(planes, clipnodes, 0).trace(start, end)
+    }
+}
+
+const FLOOR: (Vec3, Vec3) = ((-1000.0, -1000.0, -100.0), (1000.0, 1000.0, 0.0));
+
+// Feet on the floor at the origin
+fn spawn(world: &World) -> Player {
+    let trace = |hull, start, end| world.trace(hull, start, end);
+    let mut player = Player::new(&trace, (0.0, 0.0, 40.0 + STAND_VIEW_OFFSET));
+    run(world, &mut player, &Input::default(), 0.0, 50);
+    assert!(player.on_ground());
+    player
+}
+
+fn run(world: &World, player: &mut Player, input: &Input, yaw: f32, frames: usize) {
+    let trace = |hull, start, end| world.trace(hull, start, end);
+    for _ in 0..frames {
+        player.update(&trace, input, yaw, DT);
+    }
+}
+
+fn walk_to_step(height: f32) -> Player {
+    let world = World::new(&[FLOOR, ((32.0, -1000.0, 0.0), (1000.0, 1000.0, height))]);
+    let mut player = spawn(&world);
+    let forward = Input {
+        forward: true,
+        ..Input::default()
+    };
+    run(&world, &mut player, &forward, 0.0, 100);
+    player
+}
+
+#[test]
+fn climb_step() {
+    let player = walk_to_step(18.0);
+    let (x, _, z) = player.origin();
+    assert!(x > 100.0, "{}", x);
+    assert!((z - 18.0 - 36.0).abs() < 0.1, "{}", z);
+    assert!(player.on_ground());
+
+    let player = walk_to_step(19.0);
+    let (x, _, z) = player.origin();
+    assert!(x < 16.0 && x > 15.9, "{}", x);
+    assert!((z - 36.0).abs() < 0.1, "{}", z);
+}
+
+#[test]
+fn fall_to_floor() {
+    let world = World::new(&[FLOOR]);
+    let trace = |hull, start, end| world.trace(hull, start, end);
+    let mut player = Player::new(&trace, (0.0, 0.0, 200.0));
+    run(&world, &mut player, &Input::default(), 0.0, 10);
+    assert!(!player.on_ground());
+    assert!(player.velocity().2 < 0.0);
+
+    run(&world, &mut player, &Input::default(), 0.0, 100);
+    assert!(player.on_ground());
+    assert_eq!(player.velocity(), (0.0, 0.0, 0.0));
+    assert!((player.origin().2 - 36.0).abs() < 0.1);
+}
+
+#[test]
+fn stay_ducked_under_ceiling() {
+    let ceiling = ((-1000.0, -1000.0, 50.0), (1000.0, 1000.0, 100.0));
+    let world = World::new(&[FLOOR, ceiling]);
+    let trace = |hull, start, end| world.trace(hull, start, end);
+    let mut player = Player::new(&trace, (0.0, 0.0, 30.0));
+    assert!(player.ducked());
+
+    run(&world, &mut player, &Input::default(), 0.0, 50);
+    assert!(player.ducked());
+    assert!(player.on_ground());
+    assert!((player.origin().2 - 18.0).abs() < 0.1);
+
+    let world = World::new(&[FLOOR]);
+    run(&world, &mut player, &Input::default(), 0.0, 50);
+    assert!(!player.ducked());
+    assert!((player.origin().2 - 36.0).abs() < 0.1);
+}
+
+#[test]
+fn slide_along_wall() {
+    let wall = ((100.0, -1000.0, 0.0), (200.0, 1000.0, 1000.0));
+    let world = World::new(&[FLOOR, wall]);
+    let mut player = spawn(&world);
+    let forward = Input {
+        forward: true,
+        ..Input::default()
+    };
+    run(&world, &mut player, &forward, 45.0, 100);
+    let (x, y, _) = player.origin();
+    assert!(x < 84.0 && x > 83.9, "{}", x);
+    assert!(y > 150.0, "{}", y);
+    let (vx, vy, vz) = player.velocity();
+    assert!(
+        vx.abs() < 0.001 && vy > 0.0 && vz == 0.0,
+        "{:?}",
+        (vx, vy, vz)
+    );
+}
@@ -0,0 +1,77 @@
+use file::{bsp::ent::read_entities, fgd::Fgd, map::Entities, text::decode};
+use std::{error::Error, fs::read, path::PathBuf, process::exit};
+use structopt::StructOpt;
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "entcheck",
+    about = "A program to check entities of maps against game definitions"
+)]
+struct Opt {
+    #[structopt(
+        short,
+        long = "fgd",
+        parse(from_os_str),
+        required = true,
+        help = "Game definition files, later ones override earlier"
+    )]
+    fgd_paths: Vec<PathBuf>,
+    #[structopt(parse(from_os_str), required = true, help = "Bsp or .ent files")]
+    paths: Vec<PathBuf>,
+}
+
+fn main() {
+    let opt = Opt::from_args();
+    match check(&opt) {
+        Ok(0) => {}
+        Ok(issues) => {
+            println!("{} issues found", issues);
+            exit(1);
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            exit(2);
+        }
+    }
+}
+
+fn read_fgd(paths: &[PathBuf]) -> Result<Fgd> {
+    let mut fgd = Fgd::default();
+    for path in paths {
+        let file = read(path)?;
+        let defs = Fgd::parse(&decode(&file)).map_err(|err| format!("{:?}: {}", path, err))?;
+        fgd.extend(defs);
+    }
+    Ok(fgd)
+}
+
+// Files that can't be read or parsed are reported as issues, so every file is checked
+fn check(opt: &Opt) -> Result<usize> {
+    let fgd = read_fgd(&opt.fgd_paths)?;
+    let mut issues_num = 0;
+    for path in &opt.paths {
+        let entities = match read_entities(path) {
+            Ok(entities) => entities,
+            Err(err) => {
+                println!("{}: {}", path.display(), err);
+                issues_num += 1;
+                continue;
+            }
+        };
+        match Entities::parse(&entities) {
+            Ok(entities) => {
+                for issue in fgd.validate(&entities) {
+                    println!("{}: {}", path.display(), issue);
+                    issues_num += 1;
+                }
+            }
+            Err(err) => {
+                println!("{}: {}", path.display(), err);
+                issues_num += 1;
+            }
+        }
+    }
+    Ok(issues_num)
+}
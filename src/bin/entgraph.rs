@@ -0,0 +1,70 @@
+use file::{bsp::ent::read_entities, graph::Graph, map::Entities};
+use std::{error::Error, fs::write, path::PathBuf, str::FromStr};
+use structopt::StructOpt;
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+#[derive(Debug)]
+enum Format {
+    Dot,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "dot" => Ok(Self::Dot),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("Unknown format `{}`, expected dot or json", s)),
+        }
+    }
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "entgraph",
+    about = "A program to export graph of entities triggering each other"
+)]
+struct Opt {
+    #[structopt(parse(from_os_str), help = "Bsp or .ent file")]
+    path: PathBuf,
+    #[structopt(short, long, default_value = "dot", help = "dot or json")]
+    format: Format,
+    #[structopt(
+        short,
+        long,
+        parse(from_os_str),
+        help = "Output file, stdout if not set"
+    )]
+    output: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    let opt = Opt::from_args();
+    let entities = read_entities(&opt.path)?;
+    let entities = Entities::parse(&entities).map_err(|err| format!("{:?}: {}", opt.path, err))?;
+    let graph = Graph::new(&entities);
+
+    // Problems go to stderr, so they don't mix with the graph
+    for edge in graph.dangling() {
+        eprintln!("Entity {} targets missing `{}`", edge.from, edge.target);
+    }
+    for node in graph.untriggered() {
+        eprintln!(
+            "Entity {} ({}) is never triggered",
+            node.entity, node.classname
+        );
+    }
+
+    let out = match opt.format {
+        Format::Dot => graph.to_dot(),
+        Format::Json => graph.to_json(),
+    };
+    match opt.output {
+        Some(path) => write(path, out)?,
+        None => print!("{}", out),
+    }
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use file::bsp::ent::{export_entities, import_entities};
+use std::{
+    error::Error,
+    fs::{read, write},
+    path::{Path, PathBuf},
+};
+use structopt::StructOpt;
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+const ENT_EXTENSION: &str = "ent";
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "ripent", about = "A program to edit entities of bsp")]
+enum Command {
+    #[structopt(about = "Write entities lump of bsp to .ent file")]
+    Export {
+        #[structopt(parse(from_os_str))]
+        bsp_path: PathBuf,
+        #[structopt(
+            parse(from_os_str),
+            help = "Output .ent, the bsp path with .ent extension if not set"
+        )]
+        ent_path: Option<PathBuf>,
+    },
+    #[structopt(about = "Replace entities lump of bsp with .ent file")]
+    Import {
+        #[structopt(parse(from_os_str))]
+        bsp_path: PathBuf,
+        #[structopt(
+            parse(from_os_str),
+            help = "Input .ent, the bsp path with .ent extension if not set"
+        )]
+        ent_path: Option<PathBuf>,
+        #[structopt(
+            short,
+            long,
+            parse(from_os_str),
+            help = "Output bsp, the input is overwritten if not set"
+        )]
+        output: Option<PathBuf>,
+    },
+}
+
+fn main() -> Result<()> {
+    match Command::from_args() {
+        Command::Export { bsp_path, ent_path } => {
+            let ent_path = ent_path.unwrap_or_else(|| bsp_path.with_extension(ENT_EXTENSION));
+            export(&bsp_path, &ent_path)
+        }
+        Command::Import {
+            bsp_path,
+            ent_path,
+            output,
+        } => {
+            let ent_path = ent_path.unwrap_or_else(|| bsp_path.with_extension(ENT_EXTENSION));
+            import(&bsp_path, &ent_path, output.as_deref().unwrap_or(&bsp_path))
+        }
+    }
+}
+
+fn export(bsp_path: &Path, ent_path: &Path) -> Result<()> {
+    let ent = export_entities(&read(bsp_path)?)?;
+    write(ent_path, ent)?;
+    println!("Entities written to {:?}", ent_path);
+    Ok(())
+}
+
+fn import(bsp_path: &Path, ent_path: &Path, output: &Path) -> Result<()> {
+    let bsp = import_entities(&read(bsp_path)?, &read(ent_path)?)
+        .map_err(|err| format!("{:?}: {}", ent_path, err))?;
+    write(output, bsp)?;
+    println!("Entities of {:?} imported", ent_path);
+    Ok(())
+}
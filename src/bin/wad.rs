@@ -0,0 +1,217 @@
+use file::{
+    miptex::encoder::encode,
+    wad::{writer::Builder, Archive, Content, Entry},
+};
+use image::RgbaImage;
+use std::{
+    collections::{BTreeMap, HashSet},
+    error::Error,
+    fs::{create_dir_all, read, read_dir},
+    path::{Path, PathBuf},
+};
+use structopt::StructOpt;
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+const IMAGE_EXTENSIONS: [&str; 2] = ["png", "tga"];
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "wad", about = "A program to inspect and build wad archives")]
+enum Command {
+    #[structopt(about = "List entries with their type and size")]
+    List {
+        #[structopt(parse(from_os_str))]
+        wad_path: PathBuf,
+    },
+    #[structopt(about = "Extract miptex and qpic entries to png files")]
+    Extract {
+        #[structopt(parse(from_os_str))]
+        wad_path: PathBuf,
+        #[structopt(parse(from_os_str))]
+        out_dir: PathBuf,
+        #[structopt(
+            long,
+            parse(from_os_str),
+            help = "Palette (e.g. gfx/palette.lmp) for wad2 textures"
+        )]
+        palette: Option<PathBuf>,
+    },
+    #[structopt(about = "Pack png and tga files of a directory into wad3 as miptex")]
+    Pack {
+        #[structopt(parse(from_os_str))]
+        in_dir: PathBuf,
+        #[structopt(parse(from_os_str))]
+        wad_path: PathBuf,
+    },
+    #[structopt(about = "Compare two wads by entry name and content hash")]
+    Diff {
+        #[structopt(parse(from_os_str))]
+        first: PathBuf,
+        #[structopt(parse(from_os_str))]
+        second: PathBuf,
+    },
+}
+
+fn main() -> Result<()> {
+    match Command::from_args() {
+        Command::List { wad_path } => list(&wad_path),
+        Command::Extract {
+            wad_path,
+            out_dir,
+            palette,
+        } => extract(&wad_path, &out_dir, palette.as_deref()),
+        Command::Pack { in_dir, wad_path } => pack(&in_dir, &wad_path),
+        Command::Diff { first, second } => diff(&first, &second),
+    }
+}
+
+fn parse_archive<'a>(file: &'a [u8], palette: Option<&'a [u8]>) -> Result<Archive<'a>> {
+    let archive = match palette {
+        Some(palette) => Archive::parse_with_palette(file, palette),
+        None => Archive::parse(file),
+    };
+    archive.map_err(|_| "Invalid wad archive".into())
+}
+
+fn list(wad_path: &Path) -> Result<()> {
+    let file = read(wad_path)?;
+    let archive = parse_archive(&file, None)?;
+    println!(
+        "{:?}, {} entries",
+        archive.version(),
+        archive.entries().count()
+    );
+    for (name, entry) in archive.entries() {
+        println!(
+            "{:<16} {:<16} {:>8}",
+            name,
+            format!("{:?}", entry.etype()),
+            entry.data().len()
+        );
+    }
+    for name in archive.duplicates() {
+        println!("Duplicate entry `{}`", name);
+    }
+    Ok(())
+}
+
+// Names like `*lava` or `{grate` should be usable as file names on every system
+fn file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '*' | '?' | ':' | '<' | '>' | '|' | '"' | '/' | '\\' => '_',
+            c => c,
+        })
+        .collect()
+}
+
+fn decode_image(entry: &Entry) -> Option<RgbaImage> {
+    let (width, height, pixels) = match entry.decode().ok()? {
+        Content::MipTexture(miptex) => {
+            (miptex.main_width(), miptex.main_height(), miptex.pixels(0)?)
+        }
+        Content::QPic(qpic) => (qpic.width(), qpic.height(), qpic.pixels()?),
+        _ => return None,
+    };
+    RgbaImage::from_raw(width, height, pixels)
+}
+
+fn extract(wad_path: &Path, out_dir: &Path, palette: Option<&Path>) -> Result<()> {
+    let file = read(wad_path)?;
+    let palette = palette.map(read).transpose()?;
+    let archive = parse_archive(&file, palette.as_deref())?;
+    create_dir_all(out_dir)?;
+
+    // Entries shadowed by one with the same name (ignoring case) would overwrite its file
+    let mut file_names = HashSet::new();
+    let mut extracted = 0;
+    for (name, entry) in archive.entries() {
+        let file_name = file_name(name);
+        if !file_names.insert(file_name.to_ascii_lowercase()) {
+            println!("Skipping duplicate `{}`", name);
+            continue;
+        }
+        match decode_image(entry) {
+            Some(image) => {
+                image.save(out_dir.join(format!("{}.png", file_name)))?;
+                extracted += 1;
+            }
+            None => println!("Skipping `{}` ({:?})", name, entry.etype()),
+        }
+    }
+    println!("{} entries extracted", extracted);
+    Ok(())
+}
+
+fn pack(in_dir: &Path, wad_path: &Path) -> Result<()> {
+    let mut paths = read_dir(in_dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    paths.sort();
+
+    let mut builder = Builder::new();
+    for path in paths {
+        let is_image = path
+            .extension()
+            .and_then(|x| x.to_str())
+            .is_some_and(|x| IMAGE_EXTENSIONS.iter().any(|e| x.eq_ignore_ascii_case(e)));
+        let name = match path.file_stem().and_then(|x| x.to_str()) {
+            Some(name) if is_image => name,
+            _ => continue,
+        };
+
+        let image = image::open(&path)?.to_rgba8();
+        let miptex = encode(name, image.width(), image.height(), image.as_raw())
+            .and_then(|miptex| builder.insert_miptex(name, miptex));
+        if let Err(err) = miptex {
+            println!("Skipping {:?}: {}", path, err);
+        }
+    }
+    builder.save(wad_path)?;
+    println!("{} entries packed", builder.len());
+    Ok(())
+}
+
+// FNV-1a, stable between runs and platforms
+fn hash(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+// The first of entries with the same name wins, like on lookup in the archive
+fn hashes(archive: &Archive) -> BTreeMap<String, (String, u64)> {
+    let mut hashes = BTreeMap::new();
+    for (name, entry) in archive.entries() {
+        hashes
+            .entry(name.to_ascii_lowercase())
+            .or_insert_with(|| (name.to_string(), hash(entry.data())));
+    }
+    hashes
+}
+
+fn diff(first: &Path, second: &Path) -> Result<()> {
+    let (first, second) = (read(first)?, read(second)?);
+    let first = hashes(&parse_archive(&first, None)?);
+    let second = hashes(&parse_archive(&second, None)?);
+
+    let mut differences = 0;
+    for (key, (name, hash)) in &first {
+        match second.get(key) {
+            None => println!("- {}", name),
+            Some((_, other)) if other != hash => {
+                println!("~ {} {:016x} {:016x}", name, hash, other)
+            }
+            _ => continue,
+        }
+        differences += 1;
+    }
+    for (key, (name, _)) in &second {
+        if !first.contains_key(key) {
+            println!("+ {}", name);
+            differences += 1;
+        }
+    }
+    println!("{} differences", differences);
+    Ok(())
+}
@@ -0,0 +1,117 @@
+use file::{
+    bsp::embed::{embed_textures, strip_textures},
+    wad::Archive,
+};
+use std::{
+    error::Error,
+    fs::{read, write},
+    path::{Path, PathBuf},
+};
+use structopt::StructOpt;
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "wadinclude",
+    about = "A program to embed wad textures into bsp and back"
+)]
+enum Command {
+    #[structopt(about = "Copy textures found in wads into bsp, first wad wins")]
+    Embed {
+        #[structopt(parse(from_os_str))]
+        bsp_path: PathBuf,
+        #[structopt(parse(from_os_str), required = true)]
+        wad_paths: Vec<PathBuf>,
+        #[structopt(
+            short,
+            long,
+            parse(from_os_str),
+            help = "Output bsp, the input is overwritten if not set"
+        )]
+        output: Option<PathBuf>,
+    },
+    #[structopt(about = "Remove embedded textures existing in wad from bsp")]
+    Strip {
+        #[structopt(parse(from_os_str))]
+        bsp_path: PathBuf,
+        #[structopt(parse(from_os_str))]
+        wad_path: PathBuf,
+        #[structopt(
+            short,
+            long,
+            parse(from_os_str),
+            help = "Output bsp, the input is overwritten if not set"
+        )]
+        output: Option<PathBuf>,
+    },
+}
+
+fn main() -> Result<()> {
+    match Command::from_args() {
+        Command::Embed {
+            bsp_path,
+            wad_paths,
+            output,
+        } => embed(
+            &bsp_path,
+            &wad_paths,
+            output.as_deref().unwrap_or(&bsp_path),
+        ),
+        Command::Strip {
+            bsp_path,
+            wad_path,
+            output,
+        } => strip(&bsp_path, &wad_path, output.as_deref().unwrap_or(&bsp_path)),
+    }
+}
+
+fn wad_name(path: &Path) -> Result<&str> {
+    path.file_name()
+        .and_then(|x| x.to_str())
+        .ok_or_else(|| format!("Invalid wad path {:?}", path).into())
+}
+
+fn parse_archive(file: &[u8]) -> Result<Archive<'_>> {
+    Archive::parse(file).map_err(|_| "Invalid wad archive".into())
+}
+
+fn embed(bsp_path: &Path, wad_paths: &[PathBuf], output: &Path) -> Result<()> {
+    let bsp = read(bsp_path)?;
+    let files = wad_paths
+        .iter()
+        .map(read)
+        .collect::<std::io::Result<Vec<_>>>()?;
+    let archives = files
+        .iter()
+        .map(|file| parse_archive(file))
+        .collect::<Result<Vec<_>>>()?;
+    let wads = wad_paths
+        .iter()
+        .map(|path| wad_name(path))
+        .zip(&archives)
+        .map(|(name, archive)| name.map(|name| (name, archive)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let (bsp, embedded) = embed_textures(&bsp, &wads)?;
+    write(output, bsp)?;
+    embedded
+        .iter()
+        .for_each(|name| println!("Embedded `{}`", name));
+    println!("{} textures embedded", embedded.len());
+    Ok(())
+}
+
+fn strip(bsp_path: &Path, wad_path: &Path, output: &Path) -> Result<()> {
+    let bsp = read(bsp_path)?;
+    let wad = read(wad_path)?;
+    let archive = parse_archive(&wad)?;
+
+    let (bsp, stripped) = strip_textures(&bsp, wad_name(wad_path)?, &archive)?;
+    write(output, bsp)?;
+    stripped
+        .iter()
+        .for_each(|name| println!("Stripped `{}`", name));
+    println!("{} textures stripped", stripped.len());
+    Ok(())
+}
@@ -1,14 +1,30 @@
 mod support;
 
 use cgmath::Deg;
+use file::{
+    bsp::hull::Trace,
+    fgd::Fgd,
+    text::decode,
+    vfs::{FileSystem, BASE_GAME, MAPS_DIR},
+};
 use glium::{glutin, Surface};
-use render::Level;
-use std::path::{Path, PathBuf};
+use log::{error, info, warn};
+use render::{
+    texture::ImageDirectory,
+    walk::{Input, Player, STAND_VIEW_OFFSET},
+    Level, VolumeStyle,
+};
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
 use structopt::StructOpt;
-use support::{init_logger, Camera};
+use support::{init_logger, to_gl, to_quake, Camera, FileWatcher, Vec3};
 
 const MOVE_SPEED: f32 = 100.0;
-const CAMERA_OFFSET: f32 = 64.0;
+const MAX_FRAME_TIME: f32 = 0.1; // longer frames (e.g. while reloading) aren't simulated at once
+const DEFAULT_SPAWN: &str = "info_player_start";
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
 // Safe, because there's no multiple thread accessing this
 static mut MOUSE_GRABBED: bool = true;
 
@@ -18,28 +34,138 @@ static mut MOUSE_GRABBED: bool = true;
     about = "A program allows you to view hlbsp maps (bsp v30)"
 )]
 struct Opt {
-    #[structopt(short, long = "bsp", parse(from_os_str), help = "Path to bsp map")]
+    #[structopt(
+        short,
+        long = "bsp",
+        parse(from_os_str),
+        help = "Path to bsp map or its name in the game directory"
+    )]
     bsp_path: PathBuf,
+    #[structopt(
+        long = "basedir",
+        parse(from_os_str),
+        help = "Path to Half-Life directory (guessed from bsp path if omitted)"
+    )]
+    base_dir: Option<PathBuf>,
+    #[structopt(long, help = "Game (mod) directory to mount on top of valve")]
+    game: Option<String>,
     #[structopt(
         short,
         long = "wad",
         parse(from_os_str),
-        help = "Path to wad files which are required to load textures"
+        help = "Path to wad files (or their names in the game directory) to load textures"
     )]
     wad_path: Vec<PathBuf>,
     #[structopt(
         short,
         long = "skybox",
         parse(from_os_str),
-        help = "Path to directory stores skybox textures"
+        help = "Path to directory stores skybox textures (gfx/env of the game by default)"
     )]
     skybox_path: Option<PathBuf>,
+    #[structopt(
+        short,
+        long = "textures",
+        parse(from_os_str),
+        help = "Path to directory with png or tga files replacing textures of the same name"
+    )]
+    textures_path: Option<PathBuf>,
+    #[structopt(
+        long = "fgd",
+        parse(from_os_str),
+        help = "Game definition files giving sizes and colors of point entities"
+    )]
+    fgd_paths: Vec<PathBuf>,
+    #[structopt(
+        long = "markers",
+        help = "Classname patterns like `monster_*` of the only point entities to show"
+    )]
+    show_markers: Vec<String>,
+    #[structopt(long, help = "Classname patterns of point entities to hide")]
+    hide_markers: Vec<String>,
+    #[structopt(long, help = "Draw trigger brush entities as translucent volumes")]
+    triggers: bool,
+    #[structopt(
+        long,
+        possible_values = &["1", "2", "3"],
+        help = "Draw clip hull: 1 (standing), 2 (large monsters) or 3 (ducking)"
+    )]
+    clip_hull: Option<usize>,
+    #[structopt(long, help = "Draw clip hull translucent instead of wireframe")]
+    translucent: bool,
+    #[structopt(
+        long,
+        default_value = DEFAULT_SPAWN,
+        help = "Class of the entity to start at, e.g. info_player_deathmatch"
+    )]
+    spawn: String,
+    #[structopt(
+        long,
+        default_value = "0",
+        help = "Index of the spawn among ones of its class"
+    )]
+    spawn_index: usize,
+    #[structopt(
+        long,
+        number_of_values = 3,
+        allow_hyphen_values = true,
+        value_names = &["x", "y", "z"],
+        help = "Start position of the player instead of the spawn"
+    )]
+    pos: Option<Vec<f32>>,
+    #[structopt(
+        long,
+        number_of_values = 3,
+        allow_hyphen_values = true,
+        value_names = &["pitch", "yaw", "roll"],
+        help = "Start view angles instead of ones of the spawn"
+    )]
+    angles: Option<Vec<f32>>,
+    #[structopt(long, help = "Start walking with collision instead of flying (noclip)")]
+    walk: bool,
+    #[structopt(long, help = "Do not reload the level when bsp or wad files change")]
+    no_watch: bool,
 }
 
 fn main() {
     init_logger().unwrap();
     let opt = Opt::from_args();
-    start_window_loop(opt.bsp_path, &opt.wad_path, opt.skybox_path);
+    start_window_loop(opt);
+}
+
+// `<basedir>/<game>/maps/<map>.bsp` is the usual layout, so the game can be found from a map path
+fn guess_game_dir(bsp_path: &Path) -> Option<(PathBuf, String)> {
+    let maps_dir = bsp_path.canonicalize().ok()?.parent()?.to_path_buf();
+    if !maps_dir
+        .file_name()?
+        .to_str()?
+        .eq_ignore_ascii_case(MAPS_DIR)
+    {
+        return None;
+    }
+    let game_dir = maps_dir.parent()?;
+    let game = game_dir.file_name()?.to_str()?.to_string();
+    Some((game_dir.parent()?.to_path_buf(), game))
+}
+
+fn mount_game_dirs(opt: &Opt) -> FileSystem {
+    let guessed = guess_game_dir(&opt.bsp_path);
+    let base_dir = opt
+        .base_dir
+        .clone()
+        .or_else(|| guessed.as_ref().map(|(base_dir, _)| base_dir.clone()));
+    let game = opt
+        .game
+        .clone()
+        .or_else(|| guessed.map(|(_, game)| game))
+        .unwrap_or_else(|| BASE_GAME.to_string());
+
+    let mut fs = FileSystem::new();
+    if let Some(base_dir) = base_dir {
+        fs.mount_game(&base_dir, &game);
+        info!("Game `{}` mounted from {:?}", game, base_dir);
+    }
+    fs
 }
 
 fn get_window_center(window: &glutin::window::Window) -> glutin::dpi::PhysicalPosition<f64> {
@@ -59,12 +185,68 @@ fn grab_cursor(window: &glutin::window::Window) {
         .unwrap();
 }
 
+// Definitions that can't be read are skipped, markers of their classes stay default cubes
+fn read_fgd(paths: &[PathBuf]) -> Option<Fgd> {
+    if paths.is_empty() {
+        return None;
+    }
+    let mut fgd = Fgd::default();
+    for path in paths {
+        match std::fs::read(path) {
+            Ok(file) => match Fgd::parse(&decode(&file)) {
+                Ok(defs) => fgd.extend(defs),
+                Err(err) => warn!("Error parsing {:?}: {}", path, err),
+            },
+            Err(err) => warn!("Error reading {:?}: {}", path, err),
+        }
+    }
+    Some(fgd)
+}
+
+fn to_vec3(v: &[f32]) -> Vec3 {
+    (v[0], v[1], v[2])
+}
+
+// Position and angles given by user override ones of the spawn, each is applied
+// even if the other one is missing, e.g. angles without a spawn to start at
+fn find_start(opt: &Opt, level: &Level) -> (Option<Vec3>, Option<Vec3>) {
+    let spawn = level.spawn(&opt.spawn, opt.spawn_index);
+    if spawn.is_none() && opt.pos.is_none() {
+        warn!(
+            "Spawn `{}` #{} not found, the map has {} of them",
+            opt.spawn,
+            opt.spawn_index,
+            level.spawns_num(&opt.spawn)
+        );
+    }
+    let origin = opt
+        .pos
+        .as_deref()
+        .map(to_vec3)
+        .or_else(|| spawn.map(|x| x.origin));
+    let angles = opt
+        .angles
+        .as_deref()
+        .map(to_vec3)
+        .or_else(|| spawn.map(|x| x.angles));
+    (origin, angles)
+}
+
+// Hulls of the player are always ones the level can trace
+fn tracer(level: &Level) -> impl Fn(usize, Vec3, Vec3) -> Trace + '_ {
+    move |hull, start, end| {
+        level
+            .trace(hull, start, end)
+            .unwrap_or_else(|| Trace::clear(end))
+    }
+}
+
 fn ungrab_cursor(window: &glutin::window::Window) {
     window.set_cursor_visible(true);
     window.set_cursor_grab(false).unwrap();
 }
 
-fn start_window_loop<P: AsRef<Path>>(bsp_path: P, wad_path: &[P], skybox_path: Option<P>) {
+fn start_window_loop(opt: Opt) {
     let event_loop = glutin::event_loop::EventLoop::new();
     let wb = glutin::window::WindowBuilder::new()
         .with_title("hlbsp viewer")
@@ -75,10 +257,82 @@ fn start_window_loop<P: AsRef<Path>>(bsp_path: P, wad_path: &[P], skybox_path: O
     let display = glium::Display::new(wb, cb, &event_loop).unwrap();
     grab_cursor(display.gl_window().window());
 
-    let level_render = Level::new(&display, bsp_path, wad_path, skybox_path);
-    if let Some((x, y, z)) = level_render.start_point() {
-        camera.set_position(x, y + CAMERA_OFFSET, z);
+    let fs = mount_game_dirs(&opt);
+    let mut watcher = if opt.no_watch {
+        None
+    } else {
+        let paths = std::iter::once(fs.locate_map(&opt.bsp_path))
+            .chain(opt.wad_path.iter().map(|path| fs.locate_wad(path)));
+        Some(FileWatcher::new(paths, WATCH_INTERVAL))
+    };
+
+    let texture_overrides = opt.textures_path.as_ref().map(ImageDirectory::new);
+    let fgd = read_fgd(&opt.fgd_paths);
+    // Showing some classes only hides the rest first
+    let marker_rules: Vec<_> = opt
+        .show_markers
+        .first()
+        .map(|_| ("*", false))
+        .into_iter()
+        .chain(opt.show_markers.iter().map(|x| (x.as_str(), true)))
+        .chain(opt.hide_markers.iter().map(|x| (x.as_str(), false)))
+        .map(|(pattern, visible)| (pattern.to_string(), visible))
+        .collect();
+    let (bsp_path, wad_paths, skybox_path) = (
+        opt.bsp_path.clone(),
+        opt.wad_path.clone(),
+        opt.skybox_path.clone(),
+    );
+    let load_level = move |display: &glium::Display| {
+        let mut level = Level::new(
+            display,
+            &fs,
+            &bsp_path,
+            &wad_paths.iter().collect::<Vec<_>>(),
+            skybox_path.as_ref(),
+        )?;
+        if let Some(overrides) = &texture_overrides {
+            level.replace_textures(display, overrides);
+        }
+        if let Some(fgd) = &fgd {
+            level.set_definitions(display, fgd);
+        }
+        for (pattern, visible) in &marker_rules {
+            level.set_markers_visible(display, pattern, *visible);
+        }
+        Ok::<_, render::Error>(level)
+    };
+
+    let mut level_render = match load_level(&display) {
+        Ok(level) => level,
+        Err(err) => {
+            error!("{}", err);
+            return;
+        }
+    };
+    level_render.set_triggers_enabled(opt.triggers);
+    level_render.set_clip_hull(&display, opt.clip_hull);
+    if opt.translucent {
+        level_render.set_volume_style(VolumeStyle::Translucent);
+    }
+    let (origin, angles) = find_start(&opt, &level_render);
+    if let Some(origin) = origin {
+        let eyes = to_gl((origin.0, origin.1, origin.2 + STAND_VIEW_OFFSET));
+        camera.set_position(eyes.x, eyes.y, eyes.z);
+    }
+    if let Some(angles) = angles {
+        camera.set_angles(angles.0, angles.1);
     }
+    let mut player = if opt.walk {
+        Some(Player::new(
+            &tracer(&level_render),
+            to_quake(camera.position),
+        ))
+    } else {
+        None
+    };
+    let mut keys = Input::default();
+    let mut last_frame = Instant::now();
 
     let draw_params = glium::DrawParameters {
         blend: glium::Blend::alpha_blending(),
@@ -98,8 +352,42 @@ fn start_window_loop<P: AsRef<Path>>(bsp_path: P, wad_path: &[P], skybox_path: O
             glutin::event::Event::WindowEvent {
                 window_id: _,
                 event: wevent,
-            } => *control_flow = process_window(window, &wevent, &mut camera),
-            glutin::event::Event::MainEventsCleared => window.request_redraw(),
+            } => {
+                *control_flow = process_window(
+                    &display,
+                    window,
+                    &wevent,
+                    &mut camera,
+                    &mut level_render,
+                    &mut player,
+                    &mut keys,
+                )
+            }
+            glutin::event::Event::MainEventsCleared => {
+                if watcher.as_mut().is_some_and(FileWatcher::poll) {
+                    // Camera is left untouched, so the view is kept between reloads
+                    match load_level(&display) {
+                        Ok(mut level) => {
+                            level.set_details_enabled(level_render.details_enabled());
+                            level.set_markers_enabled(level_render.markers_enabled());
+                            level.set_triggers_enabled(level_render.triggers_enabled());
+                            level.set_clip_hull(&display, level_render.clip_hull());
+                            level.set_volume_style(level_render.volume_style());
+                            level_render = level;
+                            info!("Level reloaded");
+                        }
+                        Err(err) => error!("Error reloading level, keeping the old one: {}", err),
+                    }
+                }
+                let dt = last_frame.elapsed().as_secs_f32().min(MAX_FRAME_TIME);
+                last_frame = Instant::now();
+                if let Some(player) = &mut player {
+                    player.update(&tracer(&level_render), &keys, camera.yaw(), dt);
+                    let eyes = to_gl(player.eyes());
+                    camera.set_position(eyes.x, eyes.y, eyes.z);
+                }
+                window.request_redraw()
+            }
             glutin::event::Event::RedrawRequested(_) => {
                 let mut target = display.draw();
 
@@ -120,19 +408,74 @@ fn start_window_loop<P: AsRef<Path>>(bsp_path: P, wad_path: &[P], skybox_path: O
 }
 
 fn process_window(
+    display: &glium::Display,
     window: &glutin::window::Window,
     wevent: &glutin::event::WindowEvent,
     camera: &mut Camera,
+    level: &mut Level,
+    player: &mut Option<Player>,
+    keys: &mut Input,
 ) -> glutin::event_loop::ControlFlow {
     match wevent {
         glutin::event::WindowEvent::KeyboardInput { input, .. } => {
-            if input.state == glutin::event::ElementState::Pressed {
+            // Walking moves while keys are held, flying moves by steps
+            let pressed = input.state == glutin::event::ElementState::Pressed;
+            match input.virtual_keycode {
+                Some(glutin::event::VirtualKeyCode::W) => keys.forward = pressed,
+                Some(glutin::event::VirtualKeyCode::S) => keys.back = pressed,
+                Some(glutin::event::VirtualKeyCode::A) => keys.left = pressed,
+                Some(glutin::event::VirtualKeyCode::D) => keys.right = pressed,
+                Some(glutin::event::VirtualKeyCode::Space) => keys.jump = pressed,
+                Some(glutin::event::VirtualKeyCode::LControl) => keys.duck = pressed,
+                _ => (),
+            }
+            if pressed {
                 if let Some(virt_keycode) = input.virtual_keycode {
                     match virt_keycode {
-                        glutin::event::VirtualKeyCode::W => camera.move_forward(MOVE_SPEED),
-                        glutin::event::VirtualKeyCode::S => camera.move_back(MOVE_SPEED),
-                        glutin::event::VirtualKeyCode::A => camera.move_left(MOVE_SPEED),
-                        glutin::event::VirtualKeyCode::D => camera.move_right(MOVE_SPEED),
+                        glutin::event::VirtualKeyCode::W if player.is_none() => {
+                            camera.move_forward(MOVE_SPEED)
+                        }
+                        glutin::event::VirtualKeyCode::S if player.is_none() => {
+                            camera.move_back(MOVE_SPEED)
+                        }
+                        glutin::event::VirtualKeyCode::A if player.is_none() => {
+                            camera.move_left(MOVE_SPEED)
+                        }
+                        glutin::event::VirtualKeyCode::D if player.is_none() => {
+                            camera.move_right(MOVE_SPEED)
+                        }
+                        glutin::event::VirtualKeyCode::N => {
+                            *player = match player {
+                                Some(_) => None,
+                                None => {
+                                    Some(Player::new(&tracer(level), to_quake(camera.position)))
+                                }
+                            };
+                        }
+                        glutin::event::VirtualKeyCode::T => {
+                            level.set_details_enabled(!level.details_enabled())
+                        }
+                        glutin::event::VirtualKeyCode::M => {
+                            level.set_markers_enabled(!level.markers_enabled())
+                        }
+                        glutin::event::VirtualKeyCode::V => {
+                            level.set_triggers_enabled(!level.triggers_enabled())
+                        }
+                        // No hull, then hulls 1-3
+                        glutin::event::VirtualKeyCode::C => {
+                            let hull = match level.clip_hull() {
+                                None => Some(1),
+                                Some(hull) if hull < 3 => Some(hull + 1),
+                                Some(_) => None,
+                            };
+                            level.set_clip_hull(display, hull);
+                        }
+                        glutin::event::VirtualKeyCode::X => {
+                            level.set_volume_style(match level.volume_style() {
+                                VolumeStyle::Wireframe => VolumeStyle::Translucent,
+                                VolumeStyle::Translucent => VolumeStyle::Wireframe,
+                            })
+                        }
                         glutin::event::VirtualKeyCode::G => unsafe {
                             if MOUSE_GRABBED {
                                 ungrab_cursor(window);
@@ -1,6 +1,21 @@
 use cgmath::{perspective, vec3, Angle, Deg, Euler, InnerSpace, Matrix4, Point3, Rad, Vector3};
+use std::{
+    fs::metadata,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
 
 pub type Scal = f32;
+pub type Vec3 = (Scal, Scal, Scal); // Quake coordinates, Z is up
+
+// Maps are drawn rotated (see ROTATE_MAT of shaders), so Quake Z becomes Y
+pub const fn to_gl(v: Vec3) -> Point3<Scal> {
+    Point3::new(-v.1, v.2, -v.0)
+}
+
+pub const fn to_quake(p: Point3<Scal>) -> Vec3 {
+    (-p.z, -p.x, p.y)
+}
 
 pub struct Camera {
     pub aspect_ratio: Scal,
@@ -31,16 +46,22 @@ impl Camera {
 
     pub fn rotate_by(&mut self, pitch: Scal, yaw: Scal, roll: Scal) {
         self.rotation.x = Deg(self.rotation.x.0 + pitch);
-        // TODO : replace with clamp when it'll be stable
-        if self.rotation.x.0 > 89.9 {
-            self.rotation.x.0 = 89.9;
-        } else if self.rotation.x.0 < -89.9 {
-            self.rotation.x.0 = -89.9;
-        }
+        self.rotation.x.0 = self.rotation.x.0.clamp(-89.9, 89.9);
         self.rotation.y += Deg(yaw);
         self.rotation.z += Deg(roll);
     }
 
+    // Quake pitch looks down and yaw turns counterclockwise from X axis, roll isn't used
+    pub fn set_angles(&mut self, pitch: Scal, yaw: Scal) {
+        self.rotation.x = Deg((-pitch).clamp(-89.9, 89.9));
+        self.rotation.y = Deg(-(yaw + 90.0));
+        self.rotation.z = Deg(0.0);
+    }
+
+    pub fn yaw(&self) -> Scal {
+        -self.rotation.y.0 - 90.0
+    }
+
     pub const fn up() -> Vector3<Scal> {
         vec3(0.0, 1.0, 0.0)
     }
@@ -89,6 +110,51 @@ impl Camera {
     }
 }
 
+pub struct FileWatcher {
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+    interval: Duration,
+    last_check: Instant,
+}
+
+impl FileWatcher {
+    pub fn new<I: IntoIterator<Item = PathBuf>>(paths: I, interval: Duration) -> Self {
+        let files = paths
+            .into_iter()
+            .map(|path| {
+                let modified = Self::modified(&path);
+                (path, modified)
+            })
+            .collect();
+        Self {
+            files,
+            interval,
+            last_check: Instant::now(),
+        }
+    }
+
+    fn modified(path: &Path) -> Option<SystemTime> {
+        metadata(path).and_then(|m| m.modified()).ok()
+    }
+
+    // Returns true if any of files was modified since the last call
+    pub fn poll(&mut self) -> bool {
+        if self.last_check.elapsed() < self.interval {
+            return false;
+        }
+        self.last_check = Instant::now();
+
+        let mut changed = false;
+        for (path, last_modified) in &mut self.files {
+            let modified = Self::modified(path);
+            if modified.is_some() && modified != *last_modified {
+                *last_modified = modified;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
 use log::{LevelFilter, Metadata, Record, SetLoggerError};
 
 struct SimpleLogger;